@@ -0,0 +1,70 @@
+//! Module containing helpers for writing GitHub alerts read by the markdown reader as divs
+
+use std::mem;
+
+use crate::ast::{Block, Inline};
+
+/// GitHub alert types as `(class, default title, LaTeX color, Typst color)`
+const ALERTS: [(&str, &str, &str, &str); 5] = [
+    ("note", "Note", "blue", "blue"),
+    ("tip", "Tip", "green", "green"),
+    ("important", "Important", "violet", "purple"),
+    ("warning", "Warning", "orange", "orange"),
+    ("caution", "Caution", "red", "red"),
+];
+
+/// Style of a GitHub alert
+pub struct Alert {
+    /// Color name known by the LaTeX `xcolor` package
+    pub latex_color: &'static str,
+    /// Color name known by Typst
+    pub typst_color: &'static str,
+    /// Title of the alert
+    pub title: Vec<Inline>,
+}
+
+impl Alert {
+    /// Creates an alert from the classes and content of a [`Block::Div`] if one of the classes is
+    /// an alert type. A leading div with the `title` class containing a single paragraph is
+    /// removed from the content and used as the title
+    pub fn new(classes: &[String], blocks: &mut Vec<Block>) -> Option<Self> {
+        let &(_, default, latex_color, typst_color) =
+            ALERTS.iter().find(|(c, ..)| classes.iter().any(|class| class == c))?;
+        let title = match blocks.first_mut() {
+            Some(Block::Div((_, c, _), b)) if c.iter().any(|c| c == "title") =>
+                match b.as_mut_slice() {
+                    [Block::Para(i) | Block::Plain(i)] => Some(mem::take(i)),
+                    _ => None,
+                },
+            _ => None,
+        };
+        if title.is_some() {
+            blocks.remove(0);
+        }
+        let title = title.unwrap_or_else(|| vec![Inline::Str(String::from(default))]);
+        Some(Self { latex_color, typst_color, title })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title() {
+        let div = |class: &str, blocks| {
+            Block::Div((String::new(), vec![String::from(class)], Vec::new()), blocks)
+        };
+        let custom = vec![Inline::Str(String::from("Custom"))];
+        let mut blocks =
+            vec![div("title", vec![Block::Para(custom.clone())]), Block::HorizontalRule];
+        let alert = Alert::new(&[String::from("tip")], &mut blocks).unwrap();
+        assert_eq!(alert.title, custom);
+        assert_eq!(alert.latex_color, "green");
+        assert_eq!(blocks, [Block::HorizontalRule]);
+        let alert = Alert::new(&[String::from("note")], &mut blocks).unwrap();
+        assert_eq!(alert.title, [Inline::Str(String::from("Note"))]);
+        assert_eq!(blocks, [Block::HorizontalRule]);
+        assert!(Alert::new(&[String::from("other")], &mut blocks).is_none());
+    }
+}
@@ -1,13 +1,16 @@
 //! Module containing the [`Pandoc`] type for representing parsed documents
 
 use std::collections::HashMap;
-use std::iter;
+use std::fmt::{self, Display, Formatter};
+use std::iter::Peekable;
+use std::time::Duration;
+use std::{iter, mem};
 
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
 
 use crate::md_reader::inline_parser::InlineParser;
-use crate::md_reader::Links;
+use crate::md_reader::{Links, MdReaderOptions};
 
 type Bool = bool;
 type Int = i32;
@@ -28,6 +31,199 @@ pub struct Pandoc {
     pub blocks: Vec<Block>,
 }
 
+impl Pandoc {
+    /// Appends another document to this one. Blocks of `other` are added after the blocks of this
+    /// document and metadata is merged. On conflicting metadata keys the value from this document
+    /// is kept. The api version of `other` is only used if this document has none
+    pub fn append(&mut self, other: Self) {
+        if self.pandoc_api_version.is_empty() {
+            self.pandoc_api_version = other.pandoc_api_version;
+        }
+        for (key, value) in other.meta.0 {
+            self.meta.0.entry(key).or_insert(value);
+        }
+        self.blocks.extend(other.blocks);
+    }
+
+    /// Counts whitespace delimited words in the text of the document, including lists, quotes,
+    /// tables and notes. Inline code and code blocks are only counted if `code` is set, raw
+    /// elements are never counted
+    #[must_use]
+    pub fn word_count(&self, code: bool) -> usize {
+        let mut text = String::new();
+        let mut f = |node: Node<'_>| match node {
+            Node::Block(Block::Plain(i) | Block::Para(i) | Block::Header(_, _, i)) =>
+                Inline::push_words(i, &mut text, code),
+            Node::Block(Block::LineBlock(lines)) =>
+                for i in lines {
+                    Inline::push_words(i, &mut text, code);
+                },
+            Node::Block(Block::CodeBlock(_, s)) if code => {
+                text.push_str(s);
+                text.push(' ');
+            },
+            Node::Block(Block::DefinitionList(items)) =>
+                for (term, _) in items {
+                    Inline::push_words(term, &mut text, code);
+                },
+            _ => {},
+        };
+        for b in &self.blocks {
+            b.visit(&mut f);
+        }
+        text.split_whitespace().count()
+    }
+
+    /// Estimates the time needed to read the document at `wpm` words per minute, counting words
+    /// without code as in [`Self::word_count`]
+    /// # Panics
+    /// If `wpm` is 0
+    #[must_use]
+    pub fn reading_time(&self, wpm: usize) -> Duration {
+        let millis = self.word_count(false) * 60_000 / wpm;
+        Duration::from_millis(u64::try_from(millis).unwrap_or(u64::MAX))
+    }
+
+    /// Returns the targets of all [`Inline::Link`] elements in the document in document order,
+    /// including links in tables, notes and link contents
+    #[must_use]
+    pub fn collect_links(&self) -> Vec<&Target> {
+        let mut result = Vec::new();
+        for b in &self.blocks {
+            b.visit(&mut |node| {
+                if let Node::Inline(Inline::Link(_, _, t)) = node {
+                    result.push(t);
+                }
+            });
+        }
+        result
+    }
+
+    /// Returns the targets of all [`Inline::Image`] elements in the document in document order,
+    /// see [`Self::collect_links`]
+    #[must_use]
+    pub fn collect_images(&self) -> Vec<&Target> {
+        let mut result = Vec::new();
+        for b in &self.blocks {
+            b.visit(&mut |node| {
+                if let Node::Inline(Inline::Image(_, _, t)) = node {
+                    result.push(t);
+                }
+            });
+        }
+        result
+    }
+
+    /// Returns anchors of all [`Block::Header`] elements in the document in document order. The
+    /// identifier of the header is used unchanged if it's not empty, otherwise a [`github_slug`]
+    /// made unique among the other generated slugs with [`unique_slugs`]
+    #[must_use]
+    pub fn header_anchors(&self) -> Vec<String> {
+        let headers = self.headers();
+        let slugs = headers.iter().filter(|(_, attr, _)| attr.0.is_empty());
+        let mut slugs = unique_slugs(slugs.map(|(.., i)| github_slug(i))).into_iter();
+        headers
+            .iter()
+            .map(|(_, attr, _)| {
+                if attr.0.is_empty() { slugs.next().unwrap_or_default() } else { attr.0.clone() }
+            })
+            .collect()
+    }
+
+    /// Creates a table of contents of headers with a level up to `max_depth` as a nested
+    /// [`Block::BulletList`] of [`Inline::Link`] elements pointing to the [`Self::header_anchors`]
+    /// of the headers. Headers with a higher level than the previous one are nested in its item
+    #[must_use]
+    pub fn generate_toc(&self, max_depth: usize) -> Block {
+        let mut entries = self
+            .headers()
+            .into_iter()
+            .zip(self.header_anchors())
+            .filter(|((level, ..), _)| usize::try_from(*level).is_ok_and(|l| l <= max_depth))
+            .map(|((level, _, content), anchor)| {
+                let target = (format!("#{anchor}"), String::new());
+                (level, Inline::Link(attr_empty(), content.to_vec(), target))
+            })
+            .peekable();
+        Block::BulletList(Self::toc_items(&mut entries, Int::MIN))
+    }
+
+    /// Replaces every top level paragraph containing only `[TOC]` with a table of contents created
+    /// by [`Self::generate_toc`]. Headers without an identifier get their anchor from
+    /// [`Self::header_anchors`] as the identifier, so the links of the table point to them
+    pub fn insert_toc(&mut self, max_depth: usize) {
+        let placeholder = [Inline::Str(String::from("[TOC]"))];
+        let is_placeholder =
+            |b: &Block| matches!(b, Block::Para(i) | Block::Plain(i) if i == &placeholder);
+        if !self.blocks.iter().any(is_placeholder) {
+            return;
+        }
+        let toc = self.generate_toc(max_depth);
+        let mut anchors = self.header_anchors().into_iter();
+        Block::walk(&mut self.blocks, &mut |node| {
+            if let NodeMut::Block(Block::Header(_, attr, _)) = node {
+                attr.0 = anchors.next().unwrap_or_default();
+            }
+        });
+        for b in &mut self.blocks {
+            if is_placeholder(b) {
+                *b = toc.clone();
+            }
+        }
+    }
+
+    /// Returns the level, attributes and content of all [`Block::Header`] elements in the document
+    /// including headers nested in other blocks
+    fn headers(&self) -> Vec<(Int, &Attr, &[Inline])> {
+        let mut result = Vec::new();
+        for b in &self.blocks {
+            b.visit(&mut |node| {
+                if let Node::Block(Block::Header(level, attr, content)) = node {
+                    result.push((*level, attr, content.as_slice()));
+                }
+            });
+        }
+        result
+    }
+
+    /// Creates table of contents items from entries with a level of at least `level`, each item
+    /// containing the items of following entries with a higher level
+    fn toc_items<I>(entries: &mut Peekable<I>, level: Int) -> Vec<Vec<Block>>
+    where I: Iterator<Item = (Int, Inline)> {
+        let mut items = Vec::new();
+        while let Some((l, link)) = entries.next_if(|(l, _)| *l >= level) {
+            let mut item = vec![Block::Plain(vec![link])];
+            let nested = Self::toc_items(entries, l + 1);
+            if !nested.is_empty() {
+                item.push(Block::BulletList(nested));
+            }
+            items.push(item);
+        }
+        items
+    }
+
+    /// Converts pairs of raw HTML inline tags into matching elements: `<u>` into
+    /// [`Inline::Underline`], `<sub>` into [`Inline::Subscript`], `<sup>` into
+    /// [`Inline::Superscript`] and `<mark>` into an [`Inline::Span`] with the `highlight` class.
+    /// Tags without a matching closing tag are left unchanged
+    pub fn convert_html_tags(&mut self) {
+        Block::walk(&mut self.blocks, &mut |node| {
+            if let NodeMut::Inlines(i) = node {
+                *i = Inline::convert_html_tags(mem::take(i));
+            }
+        });
+    }
+
+    /// Replaces every [`Inline::Span`] and [`Block::Div`] with an empty [`Attr`] with its content
+    pub fn unwrap_empty_attrs(&mut self) {
+        Block::walk(&mut self.blocks, &mut |node| match node {
+            NodeMut::Blocks(b) => *b = Block::unwrap_empty_divs(mem::take(b)),
+            NodeMut::Inlines(i) => *i = Inline::unwrap_empty_spans(mem::take(i)),
+            NodeMut::Block(_) | NodeMut::Inline(_) => {},
+        });
+    }
+}
+
 /// Metadata for the document: title, authors, date.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 pub struct Meta(pub Map<Text, MetaValue>);
@@ -89,6 +285,117 @@ impl Block {
         Self::Header(Int::try_from(level).unwrap(), attr_empty(), inlines)
     }
 
+    /// Applies a function to every [`Block`] and [`Inline`] element nested in this block and then
+    /// to the block itself. Elements are visited in document order, including elements in
+    /// captions, table cells, citations and notes
+    pub(crate) fn visit<'a, F>(&'a self, f: &mut F)
+    where F: FnMut(Node<'a>) {
+        let all = |blocks: &'a [Self], f: &mut F| {
+            for b in blocks {
+                b.visit(f);
+            }
+        };
+        let inlines = |inlines: &'a [Inline], f: &mut F| {
+            for i in inlines {
+                i.visit(f);
+            }
+        };
+        match self {
+            Self::Plain(i) | Self::Para(i) | Self::Header(_, _, i) => inlines(i, f),
+            Self::LineBlock(lines) =>
+                for i in lines {
+                    inlines(i, f);
+                },
+            Self::BlockQuote(b) | Self::Div(_, b) => all(b, f),
+            Self::OrderedList(_, items) | Self::BulletList(items) =>
+                for b in items {
+                    all(b, f);
+                },
+            Self::DefinitionList(items) =>
+                for (term, definitions) in items {
+                    inlines(term, f);
+                    for b in definitions {
+                        all(b, f);
+                    }
+                },
+            Self::Table(_, caption, _, head, bodies, foot) => {
+                caption.visit(f);
+                let rows = head.1.iter().chain(
+                    bodies.iter().flat_map(|b| b.2.iter().chain(b.3.iter())).chain(foot.1.iter()),
+                );
+                for cell in rows.flat_map(|r| r.1.iter()) {
+                    all(&cell.4, f);
+                }
+            },
+            Self::Figure(_, caption, b) => {
+                caption.visit(f);
+                all(b, f);
+            },
+            Self::CodeBlock(..) | Self::RawBlock(..) | Self::HorizontalRule => {},
+        }
+        f(Node::Block(self));
+    }
+
+    /// Applies a function to every element and list of elements nested in a list of [`Block`]
+    /// elements, then to its elements and then to the list itself. Elements are visited in the
+    /// same order as in [`Self::visit`], an element after the elements nested in it and a list
+    /// after its elements
+    pub(crate) fn walk<F>(blocks: &mut Vec<Self>, f: &mut F)
+    where F: FnMut(NodeMut<'_>) {
+        for b in blocks.iter_mut() {
+            match b {
+                Self::Plain(i) | Self::Para(i) | Self::Header(_, _, i) => Inline::walk(i, f),
+                Self::LineBlock(lines) =>
+                    for i in lines {
+                        Inline::walk(i, f);
+                    },
+                Self::BlockQuote(b) | Self::Div(_, b) => Self::walk(b, f),
+                Self::OrderedList(_, items) | Self::BulletList(items) =>
+                    for b in items {
+                        Self::walk(b, f);
+                    },
+                Self::DefinitionList(items) =>
+                    for (term, definitions) in items {
+                        Inline::walk(term, f);
+                        for b in definitions {
+                            Self::walk(b, f);
+                        }
+                    },
+                Self::Table(_, caption, _, head, bodies, foot) => {
+                    caption.walk(f);
+                    let rows = head.1.iter_mut().chain(
+                        bodies
+                            .iter_mut()
+                            .flat_map(|b| b.2.iter_mut().chain(b.3.iter_mut()))
+                            .chain(foot.1.iter_mut()),
+                    );
+                    for cell in rows.flat_map(|r| r.1.iter_mut()) {
+                        Self::walk(&mut cell.4, f);
+                    }
+                },
+                Self::Figure(_, caption, b) => {
+                    caption.walk(f);
+                    Self::walk(b, f);
+                },
+                Self::CodeBlock(..) | Self::RawBlock(..) | Self::HorizontalRule => {},
+            }
+            f(NodeMut::Block(b));
+        }
+        f(NodeMut::Blocks(blocks));
+    }
+
+    /// Replaces every [`Block::Div`] with an empty [`Attr`] in a list with its content
+    fn unwrap_empty_divs(blocks: Vec<Self>) -> Vec<Self> {
+        let mut result = Vec::with_capacity(blocks.len());
+        for b in blocks {
+            match b {
+                Self::Div(attr, b) if attr == attr_empty() => result.extend(b),
+                b => result.push(b),
+            }
+        }
+        result
+    }
+
     /// Creates a table with the amount of columns given by the length of the `alignments`
     /// argument. Each column will have a default [`ColWidth`]. Content is given by the `rows`
     /// argument. Each row is defined by a list of [`String`] elements, each representing one
@@ -100,15 +407,17 @@ impl Block {
     /// # Panics
     /// If `rows` is empty.
     #[must_use]
-    pub fn new_table(rows: Vec<Vec<String>>, alignments: Vec<Alignment>, links: &Links) -> Self {
+    pub fn new_table(
+        rows: Vec<Vec<String>>, alignments: Vec<Alignment>, links: &Links, options: MdReaderOptions,
+    ) -> Self {
         let mut iter = rows.into_iter();
         let size = alignments.len();
         Self::Table(
             attr_empty(),
             Caption::default(),
             alignments.into_iter().map(|a| (a, ColWidth::ColWidthDefault)).collect(),
-            TableHead::new(iter.next().unwrap(), size, links),
-            vec![TableBody::new(iter, size, links)],
+            TableHead::new(iter.next().unwrap(), size, links, options),
+            vec![TableBody::new(iter, size, links, options)],
             TableFoot::default(),
         )
     }
@@ -162,6 +471,343 @@ pub enum Inline {
     None,
 }
 
+impl Inline {
+    /// Concatenates the text of a list of [`Inline`] elements ignoring all formatting. Spaces and
+    /// line breaks are converted into single spaces, raw inlines and notes are skipped
+    #[must_use]
+    pub fn stringify(inlines: &[Self]) -> String {
+        let mut result = String::new();
+        for i in inlines {
+            i.push_text(&mut result, true);
+        }
+        result
+    }
+
+    /// Normalizes a list of [`Inline`] elements and all lists nested in its elements: merges
+    /// adjacent [`Inline::Str`] elements, removes empty ones and collapses consecutive
+    /// [`Inline::Space`] elements into one
+    pub fn normalize_inlines(inlines: &mut Vec<Self>) {
+        Self::walk(inlines, &mut |node| {
+            let NodeMut::Inlines(inlines) = node else {
+                return;
+            };
+            let mut result: Vec<Self> = Vec::with_capacity(inlines.len());
+            for i in mem::take(inlines) {
+                match (result.last_mut(), i) {
+                    (_, Self::Str(s)) if s.is_empty() => {},
+                    (Some(Self::Str(last)), Self::Str(s)) => last.push_str(&s),
+                    (Some(Self::Space), Self::Space) => {},
+                    (_, i) => result.push(i),
+                }
+            }
+            *inlines = result;
+        });
+    }
+
+    /// Converts pairs of raw HTML tags in a list of [`Inline`] elements, see
+    /// [`Pandoc::convert_html_tags`]
+    fn convert_html_tags(inlines: Vec<Self>) -> Vec<Self> {
+        let mut result = Vec::with_capacity(inlines.len());
+        let mut iter = inlines.into_iter();
+        while let Some(i) = iter.next() {
+            let Some(tag) = i.html_tag(false) else {
+                result.push(i);
+                continue;
+            };
+            let mut depth = 0;
+            let mut content = Vec::new();
+            let mut closed = false;
+            for next in iter.by_ref() {
+                if next.html_tag(false) == Some(tag) {
+                    depth += 1;
+                } else if next.html_tag(true) == Some(tag) {
+                    if depth == 0 {
+                        closed = true;
+                        break;
+                    }
+                    depth -= 1;
+                }
+                content.push(next);
+            }
+            let content = Self::convert_html_tags(content);
+            if !closed {
+                result.push(i);
+                result.extend(content);
+                continue;
+            }
+            result.push(match tag {
+                "u" => Self::Underline(content),
+                "sub" => Self::Subscript(content),
+                "sup" => Self::Superscript(content),
+                _ => Self::Span(
+                    (String::new(), vec![String::from("highlight")], Vec::new()),
+                    content,
+                ),
+            });
+        }
+        result
+    }
+
+    /// Returns the name of a supported HTML tag if this element is a raw HTML opening or closing
+    /// tag
+    fn html_tag(&self, closing: bool) -> Option<&'static str> {
+        let Self::RawInline(Format(format), tag) = self else {
+            return None;
+        };
+        if format != "html" {
+            return None;
+        }
+        let tag = tag.strip_prefix('<')?.strip_suffix('>')?;
+        let tag = if closing { tag.strip_prefix('/')? } else { tag };
+        ["u", "sub", "sup", "mark"].into_iter().find(|t| t.eq_ignore_ascii_case(tag.trim_end()))
+    }
+
+    /// Replaces every [`Inline::Span`] with an empty [`Attr`] in a list with its content
+    fn unwrap_empty_spans(inlines: Vec<Self>) -> Vec<Self> {
+        let mut result = Vec::with_capacity(inlines.len());
+        for i in inlines {
+            match i {
+                Self::Span(attr, i) if attr == attr_empty() => result.extend(i),
+                i => result.push(i),
+            }
+        }
+        result
+    }
+
+    /// Applies a function to every [`Block`] and [`Inline`] element nested in this element and
+    /// then to the element itself, see [`Block::visit`]
+    pub(crate) fn visit<'a, F>(&'a self, f: &mut F)
+    where F: FnMut(Node<'a>) {
+        match self {
+            Self::Emph(i)
+            | Self::Underline(i)
+            | Self::Strong(i)
+            | Self::Strikeout(i)
+            | Self::Superscript(i)
+            | Self::Subscript(i)
+            | Self::SmallCaps(i)
+            | Self::Quoted(_, i)
+            | Self::Link(_, i, _)
+            | Self::Image(_, i, _)
+            | Self::Span(_, i) =>
+                for i in i {
+                    i.visit(f);
+                },
+            Self::Cite(citations, i) =>
+                for i in citations.iter().flat_map(|c| c.prefix.iter().chain(&c.suffix)).chain(i) {
+                    i.visit(f);
+                },
+            Self::Note(b) =>
+                for b in b {
+                    b.visit(f);
+                },
+            _ => {},
+        }
+        f(Node::Inline(self));
+    }
+
+    /// Applies a function to every element and list of elements nested in a list of [`Inline`]
+    /// elements, then to its elements and then to the list itself, see [`Block::walk`]
+    pub(crate) fn walk<F>(inlines: &mut Vec<Self>, f: &mut F)
+    where F: FnMut(NodeMut<'_>) {
+        for i in inlines.iter_mut() {
+            match i {
+                Self::Emph(i)
+                | Self::Underline(i)
+                | Self::Strong(i)
+                | Self::Strikeout(i)
+                | Self::Superscript(i)
+                | Self::Subscript(i)
+                | Self::SmallCaps(i)
+                | Self::Quoted(_, i)
+                | Self::Link(_, i, _)
+                | Self::Image(_, i, _)
+                | Self::Span(_, i) => Self::walk(i, f),
+                Self::Cite(citations, i) => {
+                    for c in citations {
+                        Self::walk(&mut c.prefix, f);
+                        Self::walk(&mut c.suffix, f);
+                    }
+                    Self::walk(i, f);
+                },
+                Self::Note(b) => Block::walk(b, f),
+                _ => {},
+            }
+            f(NodeMut::Inline(i));
+        }
+        f(NodeMut::Inlines(inlines));
+    }
+
+    /// Pushes the text of a list of elements followed by a space into a string for counting
+    /// words, see [`Pandoc::word_count`]. Notes are skipped as their blocks are counted separately
+    fn push_words(inlines: &[Self], result: &mut String, code: bool) {
+        for i in inlines {
+            i.push_text(result, code);
+        }
+        result.push(' ');
+    }
+
+    /// Pushes the text of this element into a string, inline code only if `code` is set
+    fn push_text(&self, result: &mut String, code: bool) {
+        match self {
+            Self::Code(..) if !code => {},
+            Self::Str(s) | Self::Code(_, s) | Self::Math(_, s) | Self::Temp(s) =>
+                result.push_str(s),
+            Self::Space | Self::SoftBreak | Self::LineBreak => result.push(' '),
+            Self::Quoted(q, i) => {
+                let quote = match q {
+                    QuoteType::SingleQuote => '\'',
+                    QuoteType::DoubleQuote => '"',
+                };
+                result.push(quote);
+                for i in i {
+                    i.push_text(result, code);
+                }
+                result.push(quote);
+            },
+            Self::Emph(i)
+            | Self::Underline(i)
+            | Self::Strong(i)
+            | Self::Strikeout(i)
+            | Self::Superscript(i)
+            | Self::Subscript(i)
+            | Self::SmallCaps(i)
+            | Self::Cite(_, i)
+            | Self::Link(_, i, _)
+            | Self::Image(_, i, _)
+            | Self::Span(_, i) =>
+                for i in i {
+                    i.push_text(result, code);
+                },
+            Self::RawInline(..) | Self::Note(_) | Self::None => {},
+        }
+    }
+}
+
+/// Renders the block as simplified Markdown for debugging. The output is not escaped
+impl Display for Block {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Plain(i) | Self::Para(i) => write_inlines(f, i),
+            Self::LineBlock(lines) => {
+                let lines: Vec<_> =
+                    lines.iter().map(|i| format!("| {}", inlines_string(i))).collect();
+                f.write_str(&lines.join("\n"))
+            },
+            Self::CodeBlock(_, code) => write!(f, "```\n{code}\n```"),
+            Self::RawBlock(_, raw) => f.write_str(raw),
+            Self::BlockQuote(b) => f.write_str(&prefix_lines(&blocks_string(b), "> ", "> ")),
+            Self::OrderedList((start, ..), items) => {
+                let items = items.iter().zip(*start..).map(|(b, n)| {
+                    let marker = format!("{n}. ");
+                    prefix_lines(&blocks_string(b), &marker, &" ".repeat(marker.len()))
+                });
+                f.write_str(&items.collect::<Vec<_>>().join("\n"))
+            },
+            Self::BulletList(items) => {
+                let items = items.iter().map(|b| prefix_lines(&blocks_string(b), "- ", "  "));
+                f.write_str(&items.collect::<Vec<_>>().join("\n"))
+            },
+            Self::DefinitionList(items) => {
+                let items = items.iter().map(|(term, definitions)| {
+                    let definitions = definitions
+                        .iter()
+                        .map(|b| prefix_lines(&blocks_string(b), ": ", "  "))
+                        .collect::<Vec<_>>();
+                    format!("{}\n{}", inlines_string(term), definitions.join("\n"))
+                });
+                f.write_str(&items.collect::<Vec<_>>().join("\n\n"))
+            },
+            Self::Header(level, _, i) => {
+                let level = usize::try_from(*level).unwrap_or_default();
+                write!(f, "{} ", "#".repeat(level))?;
+                write_inlines(f, i)
+            },
+            Self::HorizontalRule => f.write_str("---"),
+            Self::Table(_, _, spec, head, bodies, foot) => {
+                let row = |r: &Row| {
+                    let cells: Vec<_> = r.1.iter().map(|c| blocks_string(&c.4)).collect();
+                    format!("| {} |", cells.join(" | ").replace('\n', " "))
+                };
+                let mut rows: Vec<_> = head.1.iter().map(row).collect();
+                rows.push(format!("|{}", " - |".repeat(spec.len())));
+                let body = bodies.iter().flat_map(|b| b.2.iter().chain(b.3.iter()));
+                rows.extend(body.chain(foot.1.iter()).map(row));
+                f.write_str(&rows.join("\n"))
+            },
+            Self::Figure(_, _, b) | Self::Div(_, b) => f.write_str(&blocks_string(b)),
+        }
+    }
+}
+
+/// Renders the element as simplified Markdown for debugging, see [`Block`]
+impl Display for Inline {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let wrap = |f: &mut Formatter<'_>, marker: &str, i: &[Self]| {
+            f.write_str(marker)?;
+            write_inlines(f, i)?;
+            f.write_str(marker)
+        };
+        match self {
+            Self::Str(s) | Self::Temp(s) | Self::RawInline(_, s) => f.write_str(s),
+            Self::Emph(i) => wrap(f, "*", i),
+            Self::Underline(i) => write!(f, "<u>{}</u>", inlines_string(i)),
+            Self::Strong(i) => wrap(f, "**", i),
+            Self::Strikeout(i) => wrap(f, "~~", i),
+            Self::Superscript(i) => wrap(f, "^", i),
+            Self::Subscript(i) => wrap(f, "~", i),
+            Self::Quoted(QuoteType::SingleQuote, i) => wrap(f, "'", i),
+            Self::Quoted(QuoteType::DoubleQuote, i) => wrap(f, "\"", i),
+            Self::SmallCaps(i) | Self::Cite(_, i) | Self::Span(_, i) => write_inlines(f, i),
+            Self::Code(_, code) => {
+                let fence = if code.contains('`') { "``" } else { "`" };
+                write!(f, "{fence}{code}{fence}")
+            },
+            Self::Space => f.write_str(" "),
+            Self::SoftBreak => f.write_str("\n"),
+            Self::LineBreak => f.write_str("\\\n"),
+            Self::Math(MathType::InlineMath, math) => write!(f, "${math}$"),
+            Self::Math(MathType::DisplayMath, math) => write!(f, "$${math}$$"),
+            Self::Link(_, i, (url, _)) => write!(f, "[{}]({url})", inlines_string(i)),
+            Self::Image(_, i, (url, _)) => write!(f, "![{}]({url})", inlines_string(i)),
+            Self::Note(b) => write!(f, "^[{}]", blocks_string(b)),
+            Self::None => Ok(()),
+        }
+    }
+}
+
+/// Writes a list of [`Inline`] elements with their [`Display`] implementation
+fn write_inlines(f: &mut Formatter<'_>, inlines: &[Inline]) -> fmt::Result {
+    inlines.iter().try_for_each(|i| write!(f, "{i}"))
+}
+
+/// Renders a list of [`Inline`] elements with their [`Display`] implementation
+fn inlines_string(inlines: &[Inline]) -> String {
+    inlines.iter().map(ToString::to_string).collect()
+}
+
+/// Renders a list of [`Block`] elements with their [`Display`] implementation separated by blank
+/// lines
+fn blocks_string(blocks: &[Block]) -> String {
+    blocks.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n\n")
+}
+
+/// Prefixes the first line of a text with `first` and all other lines with `rest`, trimmed on
+/// empty lines
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    let mut result = String::new();
+    for (n, line) in text.split('\n').enumerate() {
+        if n == 0 {
+            result.push_str(first);
+        } else {
+            result.push('\n');
+            result.push_str(if line.is_empty() { rest.trim_end() } else { rest });
+        }
+        result.push_str(line);
+    }
+    result
+}
+
 /// Attributes: identifier, classes, key-value pairs
 pub type Attr = (Text, Vec<Text>, Vec<(Text, Text)>);
 
@@ -169,6 +815,59 @@ pub type Attr = (Text, Vec<Text>, Vec<(Text, Text)>);
 #[must_use]
 pub fn attr_empty() -> Attr { (String::new(), Vec::new(), Vec::new()) }
 
+/// Creates a heading anchor from its text the way GitHub does: the text is lowercased, all chars
+/// other than letters, digits, `-`, `_` and spaces are removed and spaces are replaced with `-`
+#[must_use]
+pub fn github_slug(inlines: &[Inline]) -> String {
+    Inline::stringify(inlines)
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// Makes slugs of a document unique the way GitHub does: repeated slugs get `-1`, `-2`, etc.
+/// appended, skipping suffixed slugs which are already used
+#[must_use]
+pub fn unique_slugs<I>(slugs: I) -> Vec<String>
+where I: IntoIterator<Item = String> {
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let mut result = Vec::new();
+    for slug in slugs {
+        let mut unique = slug.clone();
+        while occurrences.contains_key(&unique) {
+            let count = occurrences.entry(slug.clone()).or_default();
+            *count += 1;
+            unique = format!("{slug}-{count}");
+        }
+        occurrences.insert(unique.clone(), 0);
+        result.push(unique);
+    }
+    result
+}
+
+/// Element of a document passed to the function of [`Block::visit`]
+#[derive(Clone, Copy)]
+pub(crate) enum Node<'a> {
+    /// A block element
+    Block(&'a Block),
+    /// An inline element
+    Inline(&'a Inline),
+}
+
+/// Element or list of elements of a document passed to the function of [`Block::walk`]
+pub(crate) enum NodeMut<'a> {
+    /// A block element
+    Block(&'a mut Block),
+    /// An inline element
+    Inline(&'a mut Inline),
+    /// A list of block elements
+    Blocks(&'a mut Vec<Block>),
+    /// A list of inline elements
+    Inlines(&'a mut Vec<Inline>),
+}
+
 /// Format for [`Block::RawBlock`] and [`Inline::RawInline`]
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Default)]
 pub struct Format(pub Text);
@@ -193,6 +892,29 @@ pub fn new_list_attributes(starting: usize, closing: char) -> ListAttributes {
 #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone, Default)]
 pub struct Caption(pub Option<ShortCaption>, pub Vec<Block>);
 
+impl Caption {
+    /// Applies a function to every element nested in the caption, see [`Block::visit`]
+    fn visit<'a, F>(&'a self, f: &mut F)
+    where F: FnMut(Node<'a>) {
+        for i in self.0.iter().flatten() {
+            i.visit(f);
+        }
+        for b in &self.1 {
+            b.visit(f);
+        }
+    }
+
+    /// Applies a function to every element and list of elements in the caption, see
+    /// [`Block::walk`]
+    fn walk<F>(&mut self, f: &mut F)
+    where F: FnMut(NodeMut<'_>) {
+        if let Some(short) = &mut self.0 {
+            Inline::walk(short, f);
+        }
+        Block::walk(&mut self.1, f);
+    }
+}
+
 /// Specification of a single [`Block::Table`] column
 pub type ColSpec = (Alignment, ColWidth);
 
@@ -206,8 +928,8 @@ impl TableHead {
     /// If the row contains too many elements, the excess will be ignored and if it contains too
     /// little elements, empty cells will be added.
     #[must_use]
-    pub fn new(row: Vec<String>, size: usize, links: &Links) -> Self {
-        Self(attr_empty(), vec![Row::new(row, size, links)])
+    pub fn new(row: Vec<String>, size: usize, links: &Links, options: MdReaderOptions) -> Self {
+        Self(attr_empty(), vec![Row::new(row, size, links, options)])
     }
 }
 
@@ -222,13 +944,13 @@ impl TableBody {
     /// in the intermediate body. Each [`String`] is parsed as a [`Block::Plain`] element. If
     /// the row contains too many elements, the excess will be ignored and if it contains too
     /// little elements, empty cells will be added.
-    pub fn new<I>(rows: I, size: usize, links: &Links) -> Self
+    pub fn new<I>(rows: I, size: usize, links: &Links, options: MdReaderOptions) -> Self
     where I: Iterator<Item = Vec<String>> {
         Self(
             attr_empty(),
             RowHeadColumns(0),
             Vec::new(),
-            rows.map(|r| Row::new(r, size, links)).collect(),
+            rows.map(|r| Row::new(r, size, links, options)).collect(),
         )
     }
 }
@@ -342,12 +1064,12 @@ impl Row {
     /// of table columns. Each [`String`] is parsed as a [`Block::Plain`] element. If the row
     /// contains too many elements, the excess will be ignored and if it contains too
     /// little elements, empty cells will be added. The row will have empty [`Attr`]
-    pub fn new(row: Vec<String>, size: usize, links: &Links) -> Self {
+    pub fn new(row: Vec<String>, size: usize, links: &Links, options: MdReaderOptions) -> Self {
         let rest = size - row.len();
         Self(
             attr_empty(),
             row.into_iter()
-                .map(|s| Cell::new(&s, links))
+                .map(|s| Cell::new(&s, links, options))
                 .chain(iter::repeat_with(Cell::default).take(rest))
                 .collect(),
         )
@@ -380,8 +1102,8 @@ impl Cell {
     /// Creates a new [`Cell`]. The [`String`] will be parsed as a `[Block::Inline`]. The cell will
     /// have empty [`Attr`], `Alignment::Default` and [`RowSpan`] and [`ColSpan`] set to 1.
     #[must_use]
-    pub fn new(content: &str, links: &Links) -> Self {
-        let inlines = InlineParser::parse_lines(content, &links);
+    pub fn new(content: &str, links: &Links, options: MdReaderOptions) -> Self {
+        let inlines = InlineParser::parse_lines(content, links, options);
         Self(
             attr_empty(),
             Alignment::Default,
@@ -407,3 +1129,250 @@ pub struct ColSpan(pub Int);
 impl Default for ColSpan {
     fn default() -> Self { Self(1) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append() {
+        let meta = |pairs: &[(&str, &str)]| {
+            let pairs = pairs.iter().map(|&(k, v)| (k.to_owned(), MetaValue::String(v.to_owned())));
+            Meta(pairs.collect())
+        };
+        let mut first = Pandoc {
+            pandoc_api_version: vec![1, 23, 1],
+            meta: meta(&[("title", "First"), ("author", "A")]),
+            blocks: vec![Block::HorizontalRule],
+        };
+        let second = Pandoc {
+            pandoc_api_version: vec![1, 22],
+            meta: meta(&[("title", "Second"), ("date", "2024")]),
+            blocks: vec![Block::HorizontalRule, Block::Plain(Vec::new())],
+        };
+        first.append(second);
+        assert_eq!(first.pandoc_api_version, vec![1, 23, 1]);
+        assert_eq!(first.blocks.len(), 3);
+        assert_eq!(first.meta, meta(&[("title", "First"), ("author", "A"), ("date", "2024")]));
+    }
+
+    #[test]
+    fn convert_html_tags() {
+        let html = |s: &str| Inline::RawInline(Format(String::from("html")), String::from(s));
+        let str = |s: &str| Inline::Str(String::from(s));
+        let mut p = Pandoc {
+            blocks: vec![Block::Para(vec![
+                html("<u>"),
+                str("x"),
+                html("</u>"),
+                Inline::Space,
+                Inline::Emph(vec![
+                    html("<SUP>"),
+                    html("<sub>"),
+                    str("y"),
+                    html("</sub>"),
+                    html("</sup>"),
+                ]),
+                html("<mark>"),
+                str("z"),
+                html("</mark>"),
+                html("<u>"),
+                str("open"),
+            ])],
+            ..Default::default()
+        };
+        p.convert_html_tags();
+        let highlight = (String::new(), vec![String::from("highlight")], Vec::new());
+        assert_eq!(p.blocks, [Block::Para(vec![
+            Inline::Underline(vec![str("x")]),
+            Inline::Space,
+            Inline::Emph(vec![Inline::Superscript(vec![Inline::Subscript(vec![str("y")])])]),
+            Inline::Span(highlight, vec![str("z")]),
+            html("<u>"),
+            str("open"),
+        ])]);
+    }
+
+    #[test]
+    fn normalize_inlines() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let mut inlines = vec![
+            str("a"),
+            str(""),
+            str("b"),
+            Inline::Space,
+            Inline::Space,
+            Inline::Strong(vec![str("c"), str("d"), Inline::Emph(vec![str(""), str("e")])]),
+            Inline::Note(vec![Block::Plain(vec![str("f"), str("g")])]),
+        ];
+        Inline::normalize_inlines(&mut inlines);
+        assert_eq!(inlines, [
+            str("ab"),
+            Inline::Space,
+            Inline::Strong(vec![str("cd"), Inline::Emph(vec![str("e")])]),
+            Inline::Note(vec![Block::Plain(vec![str("fg")])]),
+        ]);
+    }
+
+    #[test]
+    fn word_count() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let para = Block::Para;
+        let code = Inline::Code(attr_empty(), String::from("let x"));
+        let note = Inline::Note(vec![para(vec![str("note")])]);
+        let cell = Cell(attr_empty(), Alignment::Default, RowSpan(1), ColSpan(1), vec![
+            Block::Plain(vec![str("cell"), Inline::Space, str("text")]),
+        ]);
+        let p = Pandoc {
+            blocks: vec![
+                Block::Header(1, attr_empty(), vec![str("Two"), Inline::Space, str("words")]),
+                para(vec![
+                    str("a"),
+                    Inline::Emph(vec![str("b")]),
+                    Inline::SoftBreak,
+                    Inline::Strong(vec![str("c"), Inline::Space, Inline::Emph(vec![str("d")])]),
+                    Inline::Space,
+                    code,
+                    note,
+                ]),
+                Block::BulletList(vec![vec![Block::BlockQuote(vec![para(vec![str("quoted")])])]]),
+                Block::Table(
+                    attr_empty(),
+                    Caption::default(),
+                    vec![(Alignment::Default, ColWidth::ColWidthDefault)],
+                    TableHead(attr_empty(), vec![Row(attr_empty(), vec![cell])]),
+                    Vec::new(),
+                    TableFoot::default(),
+                ),
+                Block::CodeBlock(attr_empty(), String::from("fn main() {}")),
+            ],
+            ..Default::default()
+        };
+        // Two words | ab c d | note | quoted | cell text
+        assert_eq!(p.word_count(false), 9);
+        // let x | fn main() {}
+        assert_eq!(p.word_count(true), 14);
+        assert_eq!(p.reading_time(180), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn collect_links() {
+        let target = |url: &str| (String::from(url), String::new());
+        let link = |url: &str| Inline::Link(attr_empty(), Vec::new(), target(url));
+        let image = Inline::Image(attr_empty(), Vec::new(), target("image.png"));
+        let linked_image = Inline::Link(attr_empty(), vec![image], target("b"));
+        let note = Inline::Note(vec![Block::Para(vec![link("c")])]);
+        let p = Pandoc {
+            blocks: vec![
+                Block::Para(vec![Inline::Emph(vec![link("a")]), note]),
+                Block::BulletList(vec![vec![Block::Plain(vec![linked_image])]]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(p.collect_links(), [&target("a"), &target("c"), &target("b")]);
+        assert_eq!(p.collect_images(), [&target("image.png")]);
+    }
+
+    #[test]
+    fn github_slugs() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let hello = [str("Hello,"), Inline::Space, str("World!")];
+        assert_eq!(github_slug(&hello), "hello-world");
+        let code = Inline::Code(attr_empty(), String::from("snake_case"));
+        let mixed = [str("Über"), Inline::Space, code, str(" - 2.0")];
+        assert_eq!(github_slug(&mixed), "über-snake_case---20");
+        let slugs = ["a", "a", "a-1", "b", "a"].map(String::from);
+        assert_eq!(unique_slugs(slugs), ["a", "a-1", "a-1-1", "b", "a-2"]);
+    }
+
+    #[test]
+    fn generate_toc() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let header = |level, s| Block::Header(level, attr_empty(), vec![str(s)]);
+        let custom = (String::from("custom"), Vec::new(), Vec::new());
+        let mut p = Pandoc {
+            blocks: vec![
+                Block::Para(vec![str("[TOC]")]),
+                header(1, "Intro"),
+                header(2, "Usage"),
+                Block::BlockQuote(vec![header(3, "Details")]),
+                header(2, "Usage"),
+                Block::Header(2, custom, vec![str("Usage")]),
+                header(1, "End"),
+            ],
+            ..Default::default()
+        };
+        let item = |s: &str, anchor: &str, nested: Vec<Vec<Block>>| {
+            let target = (format!("#{anchor}"), String::new());
+            let link = Inline::Link(attr_empty(), vec![str(s)], target);
+            let mut item = vec![Block::Plain(vec![link])];
+            if !nested.is_empty() {
+                item.push(Block::BulletList(nested));
+            }
+            item
+        };
+        let expected = Block::BulletList(vec![
+            item("Intro", "intro", vec![
+                item("Usage", "usage", vec![item("Details", "details", Vec::new())]),
+                item("Usage", "usage-1", Vec::new()),
+                item("Usage", "custom", Vec::new()),
+            ]),
+            item("End", "end", Vec::new()),
+        ]);
+        assert_eq!(p.generate_toc(6), expected);
+        let shallow = vec![item("Intro", "intro", Vec::new()), item("End", "end", Vec::new())];
+        assert_eq!(p.generate_toc(1), Block::BulletList(shallow));
+        assert_eq!(p.header_anchors(), ["intro", "usage", "details", "usage-1", "custom", "end"]);
+        p.insert_toc(6);
+        assert_eq!(p.blocks[0], expected);
+        let ids = p.headers().into_iter().map(|(_, attr, _)| attr.0.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["intro", "usage", "details", "usage-1", "custom", "end"]);
+    }
+
+    #[test]
+    fn display() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        assert_eq!(Inline::Strong(vec![Inline::Str("x".into())]).to_string(), "**x**");
+        let code = Inline::Code(attr_empty(), String::from("c"));
+        let link = Inline::Link(attr_empty(), vec![str("l")], (String::from("u"), String::new()));
+        let emph = Inline::Emph(vec![str("a"), Inline::Space, code, Inline::SoftBreak, link]);
+        assert_eq!(emph.to_string(), "*a `c`\n[l](u)*");
+        let para = |s: &str| Block::Para(vec![str(s)]);
+        let list = Block::BulletList(vec![
+            vec![para("a"), Block::BlockQuote(vec![para("b"), para("c")])],
+            vec![Block::OrderedList((3, ListNumberStyle::Decimal, ListNumberDelim::Period), vec![
+                vec![para("d")],
+                vec![Block::new_header(2, vec![str("e")])],
+            ])],
+        ]);
+        assert_eq!(list.to_string(), "- a\n\n  > b\n  >\n  > c\n- 3. d\n  4. ## e");
+    }
+
+    #[test]
+    fn unwrap_empty_attrs() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let class = (String::new(), vec![String::from("c")], Vec::new());
+        let mut p = Pandoc {
+            blocks: vec![
+                Block::Div(attr_empty(), vec![Block::Para(vec![
+                    Inline::Span(attr_empty(), vec![str("a"), Inline::Space, str("b")]),
+                    Inline::Span(class.clone(), vec![Inline::Span(attr_empty(), vec![str("c")])]),
+                ])]),
+                Block::Div(class.clone(), vec![Block::Div(attr_empty(), vec![
+                    Block::HorizontalRule,
+                ])]),
+            ],
+            ..Default::default()
+        };
+        p.unwrap_empty_attrs();
+        assert_eq!(p.blocks, [
+            Block::Para(vec![
+                str("a"),
+                Inline::Space,
+                str("b"),
+                Inline::Span(class.clone(), vec![str("c")]),
+            ]),
+            Block::Div(class, vec![Block::HorizontalRule]),
+        ]);
+    }
+}
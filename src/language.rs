@@ -0,0 +1,52 @@
+//! Module containing helpers for mapping code block languages to names known by the writers
+
+/// Markdown language hints and their names in the LaTeX `listings` package
+const LISTINGS: [(&str, &str); 17] = [
+    ("bash", "bash"),
+    ("c", "C"),
+    ("c++", "C++"),
+    ("cpp", "C++"),
+    ("html", "HTML"),
+    ("java", "Java"),
+    ("javascript", "JavaScript"),
+    ("js", "JavaScript"),
+    ("latex", "[LaTeX]TeX"),
+    ("py", "Python"),
+    ("python", "Python"),
+    ("rb", "Ruby"),
+    ("ruby", "Ruby"),
+    ("sh", "bash"),
+    ("shell", "bash"),
+    ("sql", "SQL"),
+    ("tex", "[LaTeX]TeX"),
+];
+
+/// Markdown language hints and their names in the Typst syntax highlighter
+const TYPST: [(&str, &str); 4] =
+    [("c++", "cpp"), ("console", "sh"), ("shell", "sh"), ("yml", "yaml")];
+
+/// Finds a language in a mapping table ignoring case, unknown languages are passed through
+fn find<'a>(table: &[(&str, &'a str)], language: &'a str) -> &'a str {
+    table.iter().find(|(k, _)| k.eq_ignore_ascii_case(language)).map_or(language, |(_, v)| v)
+}
+
+/// Maps a language to a name known by the LaTeX `listings` package
+pub fn listings(language: &str) -> &str { find(&LISTINGS, language) }
+
+/// Maps a language to a name known by the Typst syntax highlighter
+pub fn typst(language: &str) -> &str { find(&TYPST, language) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapping() {
+        assert_eq!(listings("js"), "JavaScript");
+        assert_eq!(listings("C++"), "C++");
+        assert_eq!(listings("sh"), "bash");
+        assert_eq!(listings("unknown"), "unknown");
+        assert_eq!(typst("c++"), "cpp");
+        assert_eq!(typst("rust"), "rust");
+    }
+}
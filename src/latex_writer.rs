@@ -4,8 +4,12 @@ use std::error::Error;
 
 use derive_more::Display;
 
-use crate::ast::{Alignment, Block, ColSpec, Inline, Pandoc, Row, TableBody, TableHead};
+use crate::ast::{
+    Alignment, Block, Citation, CitationMode, ColSpec, Inline, Pandoc, Row, TableBody, TableHead,
+};
 use crate::traits::AstWriter;
+use crate::alert::Alert;
+use crate::{language, url};
 
 /// Writes a [`Pandoc`] ast representation to LaTeX. For now only [`Block`] and `[Inline`] elements
 /// available in GitHub Flavoured Markdown are supported
@@ -13,27 +17,165 @@ use crate::traits::AstWriter;
 pub struct LatexWriter {
     result: String,
     enum_level: usize,
+    /// Number of command arguments the writer is currently inside of
+    argument_depth: usize,
+    options: LatexOptions,
+}
+
+/// Options changing the output of a [`LatexWriter`]
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct LatexOptions {
+    /// Width of images passed to `\includegraphics`, e.g. `0.8\linewidth`
+    pub image_width: String,
+    /// Whether to write a whole document with a preamble or only its body
+    pub standalone: bool,
+    /// Whether to number the lines of code blocks with `numbers=left` listing option
+    pub line_numbers: bool,
+    /// Whether to write a table of contents with `\tableofcontents` before the content
+    pub toc: bool,
+    /// Whether to color links instead of boxing them with `\hypersetup{colorlinks=true}`
+    pub colorlinks: bool,
+    /// Engine the document is meant to be compiled with, selects packages handling Unicode
+    pub engine: Engine,
+    /// Number of levels added to the level of every header. Headers shifted past level 6 are
+    /// written as paragraphs of bold text
+    pub header_offset: usize,
+}
+
+/// LaTeX engines with different Unicode support
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// `pdflatex`, uses `inputenc` with `utf8`
+    #[default]
+    Pdflatex,
+    /// `xelatex`, uses `fontspec` and `unicode-math`
+    Xelatex,
+    /// `lualatex`, uses `fontspec` and `unicode-math`
+    Lualatex,
+}
+
+impl Default for LatexOptions {
+    fn default() -> Self {
+        Self {
+            image_width: String::from("\\linewidth"),
+            standalone: true,
+            line_numbers: false,
+            toc: false,
+            colorlinks: false,
+            engine: Engine::default(),
+            header_offset: 0,
+        }
+    }
+}
+
+/// Builder for a [`LatexWriter`] returned by [`LatexWriter::builder`]
+#[derive(Debug, Clone, Default)]
+pub struct LatexWriterBuilder {
+    options: LatexOptions,
+}
+
+impl LatexWriterBuilder {
+    /// Sets [`LatexOptions::image_width`]
+    #[must_use]
+    pub fn image_width(mut self, width: impl Into<String>) -> Self {
+        self.options.image_width = width.into();
+        self
+    }
+
+    /// Sets [`LatexOptions::standalone`]
+    #[must_use]
+    pub const fn standalone(mut self, value: bool) -> Self {
+        self.options.standalone = value;
+        self
+    }
+
+    /// Sets [`LatexOptions::line_numbers`]
+    #[must_use]
+    pub const fn line_numbers(mut self, value: bool) -> Self {
+        self.options.line_numbers = value;
+        self
+    }
+
+    /// Sets [`LatexOptions::toc`]
+    #[must_use]
+    pub const fn toc(mut self, value: bool) -> Self {
+        self.options.toc = value;
+        self
+    }
+
+    /// Sets [`LatexOptions::colorlinks`]
+    #[must_use]
+    pub const fn colorlinks(mut self, value: bool) -> Self {
+        self.options.colorlinks = value;
+        self
+    }
+
+    /// Sets [`LatexOptions::engine`]
+    #[must_use]
+    pub const fn engine(mut self, value: Engine) -> Self {
+        self.options.engine = value;
+        self
+    }
+
+    /// Sets [`LatexOptions::header_offset`]
+    #[must_use]
+    pub const fn header_offset(mut self, value: usize) -> Self {
+        self.options.header_offset = value;
+        self
+    }
+
+    /// Creates a [`LatexWriter`] with the set options
+    #[must_use]
+    pub fn build(self) -> LatexWriter { LatexWriter::with_options(self.options) }
 }
 
 impl LatexWriter {
-    /// Creates a new [`LatexWriter`]
+    /// Creates a new [`LatexWriter`] with default [`LatexOptions`]
+    #[must_use]
+    pub fn new() -> Self { Self::with_options(LatexOptions::default()) }
+
+    /// Creates a new [`LatexWriter`] with given [`LatexOptions`]
+    #[must_use]
+    pub const fn with_options(options: LatexOptions) -> Self {
+        Self { result: String::new(), enum_level: 0, argument_depth: 0, options }
+    }
+
+    /// Creates a [`LatexWriterBuilder`] starting with default [`LatexOptions`]
     #[must_use]
-    pub const fn new() -> Self { Self { result: String::new(), enum_level: 0 } }
+    pub fn builder() -> LatexWriterBuilder { LatexWriterBuilder::default() }
 }
 
 impl AstWriter for LatexWriter {
     type WriteError = WriteError;
 
     fn write(mut self, ast: Pandoc) -> Result<String, Self::WriteError> {
+        if !self.options.standalone {
+            self.write_toc();
+            self.write_blocks(ast.blocks)?;
+            return Ok(self.result);
+        }
         self.push_str("\\documentclass[]{article}\n");
-        self.push_str("\\usepackage[utf8]{inputenc}\n");
+        match self.options.engine {
+            Engine::Pdflatex => self.push_str("\\usepackage[utf8]{inputenc}\n"),
+            Engine::Xelatex | Engine::Lualatex =>
+                self.push_str("\\usepackage{fontspec}\n\\usepackage{unicode-math}\n"),
+        }
         self.push_str("\\usepackage[normalem]{ulem}\n");
         self.push_str("\\usepackage{graphicx}\n");
+        self.push_str("\\usepackage{multirow}\n");
         self.push_str("\\usepackage{listings}\n");
+        self.push_str("\\usepackage{natbib}\n");
+        self.push_str("\\usepackage{tcolorbox}\n");
+        self.push_str("\\usepackage{hyperref}\n");
+        if self.options.colorlinks {
+            self.push_str("\\hypersetup{colorlinks=true}\n");
+        }
         self.push_str(
             "\\providecommand{\\tightlist}{\\setlength{\\itemsep}{0pt}\\setlength{\\parskip}{0pt}}\n",
         );
         self.push_str("\\begin{document}\n");
+        self.write_toc();
         self.write_blocks(ast.blocks)?;
         self.push_str("\n\\end{document}");
         Ok(self.result)
@@ -54,6 +196,12 @@ impl LatexWriter {
 
     fn push(&mut self, c: char) { self.result.push(c) }
 
+    fn write_toc(&mut self) {
+        if self.options.toc {
+            self.push_str("\\tableofcontents\n");
+        }
+    }
+
     fn write_blocks(&mut self, blocks: Vec<Block>) -> Result<(), WriteError> {
         for b in blocks {
             self.write_block(b)?;
@@ -71,7 +219,8 @@ impl LatexWriter {
                 self.write_inlines(p)?;
                 self.push('\n');
             },
-            Block::CodeBlock((l, ..), t) => self.write_code_block(&l, &t),
+            Block::CodeBlock((_, c, _), t) =>
+                self.write_code_block(c.first().map_or("", String::as_str), &t),
             Block::BlockQuote(b) => {
                 self.push_str("\n\\begin{quote}\n");
                 self.write_blocks(b)?;
@@ -95,16 +244,26 @@ impl LatexWriter {
                 return Err(WriteError::NotImplemented("Definition list is not yet implemented")),
             Block::Figure(..) =>
                 return Err(WriteError::NotImplemented("Figure is not yet implemented")),
-            Block::Div(..) => return Err(WriteError::NotImplemented("Div is not yet implemented")),
+            Block::Div((_, c, _), mut b) => match Alert::new(&c, &mut b) {
+                Some(alert) => self.write_alert(alert, b)?,
+                None => return Err(WriteError::NotImplemented("Div is not yet implemented")),
+            },
         };
         Ok(())
     }
 
     fn write_code_block(&mut self, language: &str, content: &str) {
         self.push_str("\n\\begin{lstlisting}");
+        let mut options = Vec::new();
         if !language.is_empty() {
-            self.push_str("[language=");
-            self.push_str(language);
+            options.push(format!("language={}", language::listings(language)));
+        }
+        if self.options.line_numbers {
+            options.push(String::from("numbers=left"));
+        }
+        if !options.is_empty() {
+            self.push('[');
+            self.push_str(&options.join(","));
             self.push(']');
         }
         self.push('\n');
@@ -112,10 +271,41 @@ impl LatexWriter {
         self.push_str("\n\\end{lstlisting}\n");
     }
 
+    /// Writes a GitHub alert as a `tcolorbox` environment colored by the alert type
+    fn write_alert(&mut self, alert: Alert, content: Vec<Block>) -> Result<(), WriteError> {
+        let color = alert.latex_color;
+        self.push_str(&format!(
+            "\n\\begin{{tcolorbox}}[colback={color}!5!white,colframe={color}!75!black,title={{"
+        ));
+        self.write_argument(alert.title)?;
+        self.push_str("}]\n");
+        self.write_blocks(content)?;
+        self.push_str("\n\\end{tcolorbox}\n");
+        Ok(())
+    }
+
+    /// Writes inline code verbatim with `\lstinline` delimited by a char not present in the
+    /// code. Inside arguments of other commands, where verbatim content breaks, or if every
+    /// delimiter is present the code is escaped inside `\texttt` instead
+    fn write_code(&mut self, code: &str) {
+        let delimiters = ['|', '!', '+', '@', '=', '/', '"', ';'];
+        let delimiter = delimiters.into_iter().find(|&c| !code.contains(c));
+        if let Some(delimiter) = delimiter.filter(|_| self.argument_depth == 0) {
+            self.push_str("\\lstinline");
+            self.push(delimiter);
+            self.push_str(code);
+            self.push(delimiter);
+        } else {
+            self.push_str("\\texttt{");
+            self.write_str(code);
+            self.push('}');
+        }
+    }
+
     fn write_ordered_list(&mut self, start: i32, items: Vec<Vec<Block>>) -> Result<(), WriteError> {
         self.push_str("\n\\begin{enumerate}");
         if start != 1 {
-            self.push_str("\nsetcounter{enum");
+            self.push_str("\n\\setcounter{enum");
             for _ in 0..self.enum_level {
                 self.push('i');
             }
@@ -127,7 +317,7 @@ impl LatexWriter {
             self.push_str("\n\\tightlist");
         }
         for i in items {
-            self.push_str("\n\\item\n");
+            self.push_str("\n\\item ");
             self.write_blocks(i)?;
         }
         self.push_str("\n\\end{enumerate}\n");
@@ -140,27 +330,30 @@ impl LatexWriter {
             self.push_str("\n\\tightlist");
         }
         for i in items {
-            self.push_str("\n\\item\n");
+            self.push_str("\n\\item ");
             self.write_blocks(i)?;
         }
         self.push_str("\n\\end{itemize}\n");
         Ok(())
     }
 
+    /// Writes a header clamping its level to the 1..=6 range, levels 5 and 6 are both written as
+    /// `\subparagraph`
     fn write_header(&mut self, level: i32, content: Vec<Inline>) -> Result<(), WriteError> {
+        let offset = self.options.header_offset;
+        let level = level.saturating_add(i32::try_from(offset).unwrap_or(i32::MAX));
+        if offset > 0 && level > 6 {
+            return self.write_block(Block::Para(vec![Inline::Strong(content)]));
+        }
         match level {
-            1 => self.push_str("\n\\section{"),
+            ..=1 => self.push_str("\n\\section{"),
             2 => self.push_str("\n\\subsection{"),
             3 => self.push_str("\n\\subsubsection{"),
             4 => self.push_str("\n\\paragraph{"),
-            5 => self.push_str("\n\\subparagraph{"),
-            _ => self.push('\n'),
-        }
-        self.write_inlines(content)?;
-        match level {
-            1..=5 => self.push_str("}\n"),
-            _ => self.push('\n'),
+            5.. => self.push_str("\n\\subparagraph{"),
         }
+        self.write_argument(content)?;
+        self.push_str("}\n");
         Ok(())
     }
 
@@ -169,36 +362,128 @@ impl LatexWriter {
     ) -> Result<(), WriteError> {
         self.push_str("\n\\begin{tabular}{|");
         let width = spec.len();
-        for (a, _) in spec {
-            self.push_str(match a {
-                Alignment::Left => "l|",
-                Alignment::Right => "r|",
-                Alignment::Center | Alignment::Default => "c|",
-            });
+        let columns: Vec<_> = spec.into_iter().map(|(a, _)| Self::alignment(a)).collect();
+        for c in &columns {
+            self.push(*c);
+            self.push('|');
         }
         self.push_str("} \\hline \n");
+        // For every column starting a cell spanning multiple rows, the number of rows it still
+        // covers below the current one and the number of columns it spans
+        let mut covered = vec![(0, 1); width];
         for r in head.into_iter().chain(body.into_iter().next().into_iter().flat_map(|b| b.3)) {
-            let row_length = r.1.len();
-            for c in r.1.into_iter().take(width) {
-                let mut c_iter = c.4.into_iter();
-                let (Some(Block::Plain(i)), None) = (c_iter.next(), c_iter.next()) else {
-                    return Err(WriteError::NotImplemented(
-                        "Tables with nested blocks aren't yet implemented",
-                    ));
+            let mut cells = r.1.into_iter();
+            let mut i = 0;
+            while i < width {
+                if covered[i].0 > 0 {
+                    // Cells covered by a \multirow from above are left empty, but still have to
+                    // span the same columns
+                    covered[i].0 -= 1;
+                    let span = covered[i].1;
+                    if span > 1 {
+                        self.write_multicolumn_start(span, columns[i], i == 0);
+                        self.push('}');
+                    }
+                    self.push('&');
+                    i += span;
+                    continue;
+                }
+                let Some(c) = cells.next() else {
+                    self.push('&');
+                    i += 1;
+                    continue;
                 };
-                self.write_inlines(i)?;
-                self.push('&');
-            }
-            for _ in 0..width.saturating_sub(row_length) {
+                let span = usize::try_from(c.3 .0).unwrap_or(1).clamp(1, width - i);
+                let rows = usize::try_from(c.2 .0).unwrap_or(1).max(1);
+                covered[i] = (rows - 1, span);
+                // A cell aligned differently than its column overrides the alignment with a
+                // \multicolumn, which also has to repeat the borders of the column
+                let alignment = (c.1 != Alignment::Default).then(|| Self::alignment(c.1));
+                let multicolumn = span > 1 || alignment.is_some_and(|a| a != columns[i]);
+                if multicolumn {
+                    self.write_multicolumn_start(span, alignment.unwrap_or(columns[i]), i == 0);
+                }
+                if rows > 1 {
+                    self.push_str("\\multirow{");
+                    self.push_str(&rows.to_string());
+                    self.push_str("}{*}{");
+                }
+                let mut c_iter = c.4.into_iter();
+                match (c_iter.next(), c_iter.next()) {
+                    (None, _) => {},
+                    (Some(Block::Plain(i)), None) if multicolumn || rows > 1 =>
+                        self.write_argument(i)?,
+                    (Some(Block::Plain(i)), None) => self.write_inlines(i)?,
+                    _ =>
+                        return Err(WriteError::NotImplemented(
+                            "Tables with nested blocks aren't yet implemented",
+                        )),
+                }
+                if rows > 1 {
+                    self.push('}');
+                }
+                if multicolumn {
+                    self.push('}');
+                }
                 self.push('&');
+                i += span;
             }
             self.result.pop();
-            self.push_str("\\\\\\hline\n");
+            self.push_str("\\\\");
+            self.write_table_rule(&covered);
         }
         self.push_str("\\end{tabular}\n");
         Ok(())
     }
 
+    /// Writes the beginning of a `\\multicolumn` up to the opening brace of its content
+    fn write_multicolumn_start(&mut self, span: usize, alignment: char, first: bool) {
+        self.push_str("\\multicolumn{");
+        self.push_str(&span.to_string());
+        self.push_str("}{");
+        if first {
+            self.push('|');
+        }
+        self.push(alignment);
+        self.push_str("|}{");
+    }
+
+    /// Writes the rule below a table row, using `\\cline` to skip columns covered by cells
+    /// spanning into the next row
+    fn write_table_rule(&mut self, covered: &[(usize, usize)]) {
+        let mut open = vec![true; covered.len()];
+        for (i, (rows, span)) in covered.iter().enumerate() {
+            if *rows > 0 {
+                open[i..(i + span).min(covered.len())].fill(false);
+            }
+        }
+        if open.iter().all(|o| *o) {
+            self.push_str("\\hline\n");
+            return;
+        }
+        let mut start = None;
+        for (i, o) in open.iter().chain([&false]).enumerate() {
+            match (o, start) {
+                (true, None) => start = Some(i + 1),
+                (false, Some(s)) => {
+                    self.push_str(&format!("\\cline{{{s}-{i}}}"));
+                    start = None;
+                },
+                _ => {},
+            }
+        }
+        self.push('\n');
+    }
+
+    /// Returns the `tabular` column type for an [`Alignment`]
+    const fn alignment(alignment: Alignment) -> char {
+        match alignment {
+            Alignment::Left => 'l',
+            Alignment::Right => 'r',
+            Alignment::Center | Alignment::Default => 'c',
+        }
+    }
+
     fn is_list_loose(list: &[Vec<Block>]) -> bool {
         list.iter()
             .flat_map(|v| v.iter())
@@ -210,6 +495,65 @@ impl LatexWriter {
             .unwrap_or(false)
     }
 
+    /// Writes citations using natbib commands: `\cite` for [`CitationMode::NormalCitation`],
+    /// `\citet` for [`CitationMode::AuthorInText`] and `\citeyearpar` for
+    /// [`CitationMode::SuppressAuthor`]. Consecutive citations with the same mode and without a
+    /// prefix or a suffix are joined into one command, other citations get separate commands
+    fn write_citations(&mut self, citations: Vec<Citation>) -> Result<(), WriteError> {
+        let mut iter = citations.into_iter().peekable();
+        while let Some(first) = iter.next() {
+            let bare = Self::is_bare_citation(&first);
+            let mut ids = first.id;
+            while let Some(next) =
+                iter.next_if(|c| bare && c.mode == first.mode && Self::is_bare_citation(c))
+            {
+                ids.push(',');
+                ids.push_str(&next.id);
+            }
+            self.push_str(match first.mode {
+                CitationMode::NormalCitation => "\\cite",
+                CitationMode::AuthorInText => "\\citet",
+                CitationMode::SuppressAuthor => "\\citeyearpar",
+            });
+            let suffix = Self::trim_citation_suffix(first.suffix);
+            if !first.prefix.is_empty() {
+                self.push('[');
+                self.write_argument(first.prefix)?;
+                self.push_str("][");
+                self.write_argument(suffix)?;
+                self.push(']');
+            } else if !suffix.is_empty() {
+                self.push('[');
+                self.write_argument(suffix)?;
+                self.push(']');
+            }
+            self.push('{');
+            self.push_str(&ids);
+            self.push('}');
+        }
+        Ok(())
+    }
+
+    /// Returns whether a citation has no prefix and no suffix
+    fn is_bare_citation(citation: &Citation) -> bool {
+        citation.prefix.is_empty() && Self::trim_citation_suffix(citation.suffix.clone()).is_empty()
+    }
+
+    /// Removes the comma and spaces separating a citation suffix from the citation id
+    fn trim_citation_suffix(mut suffix: Vec<Inline>) -> Vec<Inline> {
+        if let Some(Inline::Str(s)) = suffix.first_mut() {
+            if let Some(rest) = s.strip_prefix(',') {
+                *s = rest.to_owned();
+            }
+        }
+        let start = suffix
+            .iter()
+            .position(|i| !matches!(i, Inline::Space) && *i != Inline::Str(String::new()))
+            .unwrap_or(suffix.len());
+        suffix.drain(..start);
+        suffix
+    }
+
     fn write_inlines(&mut self, inlines: Vec<Inline>) -> Result<(), WriteError> {
         for i in inlines {
             self.write_inline(i)?;
@@ -217,42 +561,61 @@ impl LatexWriter {
         Ok(())
     }
 
+    /// Writes inlines used as an argument of a command
+    fn write_argument(&mut self, inlines: Vec<Inline>) -> Result<(), WriteError> {
+        self.argument_depth += 1;
+        let result = self.write_inlines(inlines);
+        self.argument_depth -= 1;
+        result
+    }
+
     fn write_inline(&mut self, inline: Inline) -> Result<(), WriteError> {
         match inline {
             Inline::Str(s) => self.write_str(&s),
             Inline::Emph(i) => {
                 self.push_str("\\emph{");
-                self.write_inlines(i)?;
+                self.write_argument(i)?;
                 self.push('}');
             },
             Inline::Strong(i) => {
                 self.push_str("\\textbf{");
-                self.write_inlines(i)?;
+                self.write_argument(i)?;
                 self.push('}');
             },
             Inline::Strikeout(i) => {
                 self.push_str("\\sout{");
-                self.write_inlines(i)?;
-                self.push('}');
-            },
-            Inline::Code(_, s) => {
-                self.push_str("\\texttt{");
-                self.write_str(&s);
+                self.write_argument(i)?;
                 self.push('}');
             },
+            Inline::Code(_, s) => self.write_code(&s),
             Inline::Space | Inline::SoftBreak => self.push(' '),
             Inline::LineBreak => self.push_str("\\\\\n"),
-            Inline::Link(_, _, (u, t)) => {
+            Inline::Link(_, i, (u, t)) => {
                 self.push_str("\\href{");
-                self.push_str(&u);
+                self.write_url(&u);
                 self.push_str("}{");
-                self.push_str(&t);
+                if i.is_empty() {
+                    self.write_str(&t);
+                } else {
+                    self.write_argument(i)?;
+                }
                 self.push('}');
             },
-            Inline::Image(_, _, (u, _)) => {
-                self.push_str("\n\\includegraphics[width=\\linewidth]{");
-                self.push_str(&u);
-                self.push_str("}\n");
+            Inline::Image(_, alt, (u, _)) => {
+                self.push_str("\n\\includegraphics[width=");
+                self.result.push_str(&self.options.image_width);
+                self.push_str("]{");
+                if url::is_remote(&u) {
+                    self.write_url(&u);
+                } else {
+                    self.push_str(&u);
+                }
+                self.push('}');
+                if !alt.is_empty() {
+                    self.push_str(" % alt: ");
+                    self.push_str(&Inline::stringify(&alt));
+                }
+                self.push('\n');
             },
             Inline::Underline(_) =>
                 return Err(WriteError::NotImplemented("Underline is not yet implemented")),
@@ -264,8 +627,7 @@ impl LatexWriter {
                 return Err(WriteError::NotImplemented("Small caps is not yet implemented")),
             Inline::Quoted(..) =>
                 return Err(WriteError::NotImplemented("Quoted is not yet implemented")),
-            Inline::Cite(..) =>
-                return Err(WriteError::NotImplemented("Cite is not yet implemented")),
+            Inline::Cite(c, _) => self.write_citations(c)?,
             Inline::Math(..) =>
                 return Err(WriteError::NotImplemented("Math is not yet implemented")), //???
             Inline::RawInline(..) =>
@@ -274,12 +636,23 @@ impl LatexWriter {
                 return Err(WriteError::NotImplemented("Note is not yet implemented")),
             Inline::Span(..) =>
                 return Err(WriteError::NotImplemented("Span is not yet implemented")),
-            Inline::Temp(_) => todo!(),
-            Inline::None => todo!(),
+            Inline::Temp(_) | Inline::None =>
+                return Err(WriteError::NotImplemented("Reader-internal inline can't be written")),
         }
         Ok(())
     }
 
+    /// Writes a percent-encoded url escaping `'%'` and `'#'` so it can be used inside of arguments
+    /// of other commands
+    fn write_url(&mut self, url: &str) {
+        for c in url::percent_encode(url).chars() {
+            if matches!(c, '%' | '#') {
+                self.push('\\');
+            }
+            self.push(c);
+        }
+    }
+
     fn write_str(&mut self, str: &str) {
         for c in str.chars() {
             self.write_char(c);
@@ -304,6 +677,8 @@ impl LatexWriter {
 #[cfg(test)]
 mod test {
     use crate::ast::*;
+    use crate::md_reader::MdReader;
+    use crate::traits::AstReader;
 
     use super::*;
 
@@ -315,6 +690,22 @@ mod test {
         document[start..end].trim()
     }
 
+    #[test]
+    fn header_levels() {
+        let header = |l| Block::Header(l, attr_empty(), vec![Inline::Str(String::from("a"))]);
+        let p = Pandoc { blocks: vec![header(8), header(0)], ..Default::default() };
+        let result = LatexWriter::new().write(p).unwrap();
+        assert_eq!(get_content(&result), "\\subparagraph{a}\n\n\\section{a}");
+    }
+
+    #[test]
+    fn header_offset() {
+        let header = |l| Block::Header(l, attr_empty(), vec![Inline::Str(String::from("a"))]);
+        let p = Pandoc { blocks: vec![header(1), header(6)], ..Default::default() };
+        let result = LatexWriter::builder().header_offset(1).build().write(p).unwrap();
+        assert_eq!(get_content(&result), "\\subsection{a}\n\n\\textbf{a}");
+    }
+
     #[test]
     fn special_chars() {
         let p = Pandoc {
@@ -341,4 +732,362 @@ mod test {
         let expected = "str";
         assert_eq!(content, expected);
     }
+
+    #[test]
+    fn link_text() {
+        let p = Pandoc {
+            pandoc_api_version: Vec::new(),
+            meta: Meta::default(),
+            blocks: vec![Block::Plain(vec![Inline::Link(
+                attr_empty(),
+                vec![Inline::Str(String::from("x.com"))],
+                (String::from("https://x.com"), String::new()),
+            )])],
+        };
+        let result = LatexWriter::new().write(p).unwrap();
+        let content = get_content(&result);
+        assert_eq!(content, "\\href{https://x.com}{x.com}");
+    }
+
+    #[test]
+    fn linked_image() {
+        let pandoc = MdReader::new().read("[![alt](img.png)](https://x.com)").unwrap();
+        let result = LatexWriter::new().write(pandoc).unwrap();
+        let image = "\\includegraphics[width=\\linewidth]{img.png} % alt: alt";
+        assert_eq!(get_content(&result), format!("\\href{{https://x.com}}{{\n{image}\n}}"));
+    }
+
+    #[test]
+    fn link_url_encoding() {
+        let link = Inline::Link(
+            attr_empty(),
+            vec![Inline::Str(String::from("x"))],
+            (String::from("https://x.com/a b#c"), String::new()),
+        );
+        let p = Pandoc { blocks: vec![Block::Plain(vec![link])], ..Default::default() };
+        let result = LatexWriter::new().write(p).unwrap();
+        assert_eq!(get_content(&result), "\\href{https://x.com/a\\%20b\\#c}{x}");
+    }
+
+    #[test]
+    fn image_width() {
+        let p = Pandoc {
+            pandoc_api_version: Vec::new(),
+            meta: Meta::default(),
+            blocks: vec![Block::Plain(vec![Inline::Image(
+                attr_empty(),
+                Vec::new(),
+                (String::from("image.png"), String::new()),
+            )])],
+        };
+        let options =
+            LatexOptions { image_width: String::from("0.8\\linewidth"), ..Default::default() };
+        let result = LatexWriter::with_options(options).write(p).unwrap();
+        let content = get_content(&result);
+        assert_eq!(content, "\\includegraphics[width=0.8\\linewidth]{image.png}");
+    }
+
+    #[test]
+    fn image_alt() {
+        let p = Pandoc {
+            pandoc_api_version: Vec::new(),
+            meta: Meta::default(),
+            blocks: vec![Block::Plain(vec![Inline::Image(
+                attr_empty(),
+                vec![Inline::Str(String::from("a")), Inline::Space, Inline::Str(String::from("b"))],
+                (String::from("image.png"), String::new()),
+            )])],
+        };
+        let result = LatexWriter::new().write(p).unwrap();
+        let content = get_content(&result);
+        assert_eq!(content, "\\includegraphics[width=\\linewidth]{image.png} % alt: a b");
+    }
+
+    #[test]
+    fn multi_paragraph_item() {
+        let para = |s: &str| Block::Para(vec![Inline::Str(String::from(s))]);
+        let p = Pandoc {
+            pandoc_api_version: Vec::new(),
+            meta: Meta::default(),
+            blocks: vec![Block::BulletList(vec![vec![para("a"), para("b")], vec![para("c")]])],
+        };
+        let result = LatexWriter::new().write(p).unwrap();
+        let content = get_content(&result);
+        let expected = "\\begin{itemize}\n\\item \na\n\nb\n\n\\item \nc\n\n\\end{itemize}";
+        assert_eq!(content, expected);
+    }
+
+    #[test]
+    fn citations() {
+        let citation = |id: &str, mode: CitationMode, suffix: Vec<Inline>| Citation {
+            id: String::from(id),
+            prefix: Vec::new(),
+            suffix,
+            mode,
+            note_num: 0,
+            hash: 0,
+        };
+        let write = |citations: Vec<Citation>| {
+            let p = Pandoc {
+                pandoc_api_version: Vec::new(),
+                meta: Meta::default(),
+                blocks: vec![Block::Plain(vec![Inline::Cite(citations, Vec::new())])],
+            };
+            let result = LatexWriter::new().write(p).unwrap();
+            String::from(get_content(&result))
+        };
+        let result = write(vec![citation("key", CitationMode::NormalCitation, Vec::new())]);
+        assert_eq!(result, "\\cite{key}");
+        let suffix = vec![
+            Inline::Str(String::from(",")),
+            Inline::Space,
+            Inline::Str(String::from("p.")),
+            Inline::Space,
+            Inline::Str(String::from("5")),
+        ];
+        let result = write(vec![
+            citation("a", CitationMode::AuthorInText, Vec::new()),
+            citation("b", CitationMode::NormalCitation, Vec::new()),
+            citation("c", CitationMode::NormalCitation, Vec::new()),
+            citation("d", CitationMode::NormalCitation, suffix.clone()),
+            citation("e", CitationMode::NormalCitation, suffix),
+            citation("f", CitationMode::SuppressAuthor, Vec::new()),
+        ]);
+        let expected = "\\citet{a}\\cite{b,c}\\cite[p. 5]{d}\\cite[p. 5]{e}\\citeyearpar{f}";
+        assert_eq!(result, expected);
+        let mut prefixed = citation("b", CitationMode::NormalCitation, Vec::new());
+        prefixed.prefix = vec![Inline::Str(String::from("see"))];
+        let result = write(vec![
+            citation("a", CitationMode::NormalCitation, Vec::new()),
+            prefixed,
+            citation("c", CitationMode::NormalCitation, Vec::new()),
+        ]);
+        assert_eq!(result, "\\cite{a}\\cite[see][]{b}\\cite{c}");
+    }
+
+    #[test]
+    fn builder() {
+        let p = Pandoc {
+            pandoc_api_version: Vec::new(),
+            meta: Meta::default(),
+            blocks: vec![Block::Plain(vec![Inline::Image(
+                attr_empty(),
+                Vec::new(),
+                (String::from("image.png"), String::new()),
+            )])],
+        };
+        let writer = LatexWriter::builder().standalone(false).image_width("80pt").build();
+        let result = writer.write(p).unwrap();
+        assert_eq!(result, "\n\\includegraphics[width=80pt]{image.png}\n");
+    }
+
+    #[test]
+    fn empty_cells() {
+        let cell = |s: &str| {
+            let content = vec![Block::Plain(vec![Inline::Str(String::from(s))])];
+            Cell(attr_empty(), Alignment::Default, RowSpan(1), ColSpan(1), content)
+        };
+        let rows = vec![
+            Row(attr_empty(), vec![cell("1")]),
+            Row(attr_empty(), vec![Cell::default(), Cell::default()]),
+        ];
+        let table = Block::Table(
+            attr_empty(),
+            Caption::default(),
+            vec![(Alignment::Default, ColWidth::ColWidthDefault); 2],
+            TableHead(attr_empty(), vec![Row(attr_empty(), vec![cell("a"), cell("b")])]),
+            vec![TableBody(attr_empty(), RowHeadColumns(0), Vec::new(), rows)],
+            TableFoot::default(),
+        );
+        let p = Pandoc { blocks: vec![table], ..Default::default() };
+        let result = LatexWriter::new().write(p).unwrap();
+        let expected = "\\begin{tabular}{|c|c|} \\hline \na&b\\\\\\hline\n1&\\\\\\hline\n\
+                        &\\\\\\hline\n\\end{tabular}";
+        assert_eq!(get_content(&result), expected);
+    }
+
+    #[test]
+    fn cell_alignment() {
+        let cell = |s: &str, a| {
+            let content = vec![Block::Plain(vec![Inline::Str(String::from(s))])];
+            Cell(attr_empty(), a, RowSpan(1), ColSpan(1), content)
+        };
+        let row = |a, b| Row(attr_empty(), vec![cell("a", a), cell("b", b)]);
+        let rows = vec![
+            row(Alignment::Left, Alignment::Right),
+            row(Alignment::Right, Alignment::Left),
+        ];
+        let table = Block::Table(
+            attr_empty(),
+            Caption::default(),
+            vec![(Alignment::Left, ColWidth::ColWidthDefault); 2],
+            TableHead(attr_empty(), vec![row(Alignment::Default, Alignment::Default)]),
+            vec![TableBody(attr_empty(), RowHeadColumns(0), Vec::new(), rows)],
+            TableFoot::default(),
+        );
+        let p = Pandoc { blocks: vec![table], ..Default::default() };
+        let result = LatexWriter::new().write(p).unwrap();
+        let expected = "\\begin{tabular}{|l|l|} \\hline \na&b\\\\\\hline\n\
+                        a&\\multicolumn{1}{r|}{b}\\\\\\hline\n\
+                        \\multicolumn{1}{|r|}{a}&b\\\\\\hline\n\\end{tabular}";
+        assert_eq!(get_content(&result), expected);
+    }
+
+    #[test]
+    fn cell_spans() {
+        let cell = |s: &str, rows, cols| {
+            let content = vec![Block::Plain(vec![Inline::Str(String::from(s))])];
+            Cell(attr_empty(), Alignment::Default, RowSpan(rows), ColSpan(cols), content)
+        };
+        let rows = vec![
+            Row(attr_empty(), vec![cell("a", 2, 1), cell("b", 1, 1)]),
+            Row(attr_empty(), vec![cell("c", 1, 1)]),
+        ];
+        let table = Block::Table(
+            attr_empty(),
+            Caption::default(),
+            vec![(Alignment::Left, ColWidth::ColWidthDefault); 2],
+            TableHead(attr_empty(), vec![Row(attr_empty(), vec![cell("h", 1, 2)])]),
+            vec![TableBody(attr_empty(), RowHeadColumns(0), Vec::new(), rows)],
+            TableFoot::default(),
+        );
+        let p = Pandoc { blocks: vec![table], ..Default::default() };
+        let result = LatexWriter::new().write(p).unwrap();
+        let expected = "\\begin{tabular}{|l|l|} \\hline \n\\multicolumn{2}{|l|}{h}\\\\\\hline\n\
+                        \\multirow{2}{*}{a}&b\\\\\\cline{2-2}\n&c\\\\\\hline\n\\end{tabular}";
+        assert_eq!(get_content(&result), expected);
+        assert!(result.contains("\\usepackage{multirow}\n"));
+    }
+
+    #[test]
+    fn code_block_verbatim() {
+        for content in ["a\n", "a\n\n", "a", "", "&%$#_{}~^\\`\n  \\section{x}"] {
+            let p = Pandoc {
+                blocks: vec![Block::CodeBlock(attr_empty(), String::from(content))],
+                ..Default::default()
+            };
+            let result = LatexWriter::builder().standalone(false).build().write(p).unwrap();
+            let inner = result.strip_prefix("\n\\begin{lstlisting}\n").unwrap();
+            assert_eq!(inner.strip_suffix("\n\\end{lstlisting}\n").unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn code_block_language() {
+        let code = |l: &str| Block::CodeBlock(
+            (String::new(), vec![String::from(l)], Vec::new()),
+            String::from("x"),
+        );
+        let p = Pandoc { blocks: vec![code("js"), code("rust")], ..Default::default() };
+        let result = LatexWriter::builder().standalone(false).build().write(p).unwrap();
+        assert!(result.contains("\\begin{lstlisting}[language=JavaScript]"));
+        assert!(result.contains("\\begin{lstlisting}[language=rust]"));
+    }
+
+    #[test]
+    fn code_block_line_numbers() {
+        let code = |classes: Vec<String>| {
+            let attr = (String::new(), classes, Vec::new());
+            Pandoc { blocks: vec![Block::CodeBlock(attr, String::from("x"))], ..Default::default() }
+        };
+        let writer = || LatexWriter::builder().standalone(false).line_numbers(true).build();
+        let js = || code(vec![String::from("js")]);
+        let result = writer().write(js()).unwrap();
+        assert!(result.contains("\\begin{lstlisting}[language=JavaScript,numbers=left]"));
+        let result = writer().write(code(Vec::new())).unwrap();
+        assert!(result.contains("\\begin{lstlisting}[numbers=left]"));
+        let result = LatexWriter::builder().standalone(false).build().write(js()).unwrap();
+        assert!(!result.contains("numbers=left"));
+    }
+
+    #[test]
+    fn toc() {
+        let header = Block::Header(1, attr_empty(), vec![Inline::Str(String::from("a"))]);
+        let p = Pandoc { blocks: vec![header], ..Default::default() };
+        let result = LatexWriter::builder().toc(true).build().write(p.clone()).unwrap();
+        assert_eq!(get_content(&result), "\\tableofcontents\n\n\\section{a}");
+        assert!(!LatexWriter::new().write(p).unwrap().contains("\\tableofcontents"));
+    }
+
+    #[test]
+    fn alert() {
+        let note = (String::new(), vec![String::from("note")], Vec::new());
+        let p = Pandoc {
+            blocks: vec![Block::Div(note, vec![Block::Para(vec![Inline::Str(String::from("a"))])])],
+            ..Default::default()
+        };
+        let result = LatexWriter::new().write(p).unwrap();
+        assert!(result.contains("\\usepackage{tcolorbox}"));
+        assert_eq!(
+            get_content(&result),
+            "\\begin{tcolorbox}[colback=blue!5!white,colframe=blue!75!black,title={Note}]\n\n\
+             a\n\n\\end{tcolorbox}"
+        );
+    }
+
+    #[test]
+    fn hyperref() {
+        let link = Inline::Link(attr_empty(), vec![Inline::Str(String::from("a"))], (
+            String::from("https://example.com"),
+            String::new(),
+        ));
+        let p = Pandoc { blocks: vec![Block::Plain(vec![link])], ..Default::default() };
+        let result = LatexWriter::new().write(p.clone()).unwrap();
+        assert!(result.contains("\\usepackage{hyperref}\n"));
+        assert!(!result.contains("\\hypersetup"));
+        assert_eq!(get_content(&result), "\\href{https://example.com}{a}");
+        let result = LatexWriter::builder().colorlinks(true).build().write(p).unwrap();
+        assert!(result.contains("\\usepackage{hyperref}\n\\hypersetup{colorlinks=true}\n"));
+    }
+
+    #[test]
+    fn engine() {
+        let text = Block::Plain(vec![Inline::Str(String::from("ą"))]);
+        let p = Pandoc { blocks: vec![text], ..Default::default() };
+        let result = LatexWriter::new().write(p.clone()).unwrap();
+        assert!(result.contains("\\usepackage[utf8]{inputenc}\n"));
+        assert!(!result.contains("fontspec"));
+        for engine in [Engine::Xelatex, Engine::Lualatex] {
+            let result = LatexWriter::builder().engine(engine).build().write(p.clone()).unwrap();
+            assert!(result.contains("\\usepackage{fontspec}\n\\usepackage{unicode-math}\n"));
+            assert!(!result.contains("inputenc"));
+            assert_eq!(get_content(&result), "ą");
+        }
+    }
+
+    #[test]
+    fn code_verbatim() {
+        let code = |s: &str| {
+            let p = Pandoc {
+                blocks: vec![Block::Plain(vec![Inline::Code(attr_empty(), String::from(s))])],
+                ..Default::default()
+            };
+            LatexWriter::builder().standalone(false).build().write(p).unwrap()
+        };
+        assert_eq!(code("a\\b {c} %d"), "\\lstinline|a\\b {c} %d|");
+        assert_eq!(code("a|b"), "\\lstinline!a|b!");
+        assert_eq!(code("|!+@=/\";\\"), "\\texttt{|!+@=/\";\\textbackslash{}}");
+    }
+
+    #[test]
+    fn code_in_arguments() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let code = |s: &str| Inline::Code(attr_empty(), String::from(s));
+        let p = Pandoc {
+            blocks: vec![
+                Block::Header(1, attr_empty(), vec![
+                    str("Use"),
+                    Inline::Space,
+                    code("a%b"),
+                    Inline::Space,
+                    str("here"),
+                ]),
+                Block::Plain(vec![Inline::Emph(vec![str("emph"), Inline::Space, code("x#y")])]),
+            ],
+            ..Default::default()
+        };
+        let result = LatexWriter::builder().standalone(false).build().write(p).unwrap();
+        let expected = "\n\\section{Use \\texttt{a\\%b} here}\n\\emph{emph \\texttt{x\\#y}}";
+        assert_eq!(result, expected);
+    }
 }
@@ -3,15 +3,49 @@
 //! This library provides a Pandoc compatible type for representing a
 //! parsed document, traits for parsing documents into and from this
 //! type as well as implementations for a gfm reader and LaTeX and
-//! Typst writers.
+//! Typst writers. Reading and writing Pandoc JSON is available
+//! with the `native` feature, which is enabled by default.
 
 #![warn(clippy::pedantic, clippy::nursery)]
 
+mod alert;
 pub mod ast;
+mod language;
 pub mod latex_writer;
 pub mod maps;
 pub mod md_reader;
+pub mod md_writer;
+#[cfg(feature = "native")]
 pub mod native_reader;
+#[cfg(feature = "native")]
 pub mod native_writer;
 pub mod traits;
 pub mod typst_writer;
+mod url;
+
+use std::error::Error;
+
+use maps::{ReaderMap, WriterMap};
+
+/// Converts a document between formats given by their names with the readers and writers
+/// available in the library using default options. Names are case insensitive
+/// # Errors
+/// Returns [`maps::FormatNotFound`] if there is no reader or writer with the given name or an
+/// error received from the reader or writer, all as a boxed trait object
+pub fn convert(input: &str, from: &str, to: &str) -> Result<String, Box<dyn Error>> {
+    let pandoc = ReaderMap::with_defaults().read(from, input)?;
+    WriterMap::with_defaults().write(to, pandoc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_by_name() {
+        let result = convert("# Hi", "gfm", "latex").unwrap();
+        assert!(result.contains("\\section{Hi}"));
+        assert_eq!(convert("# Hi", "gfm", "typst").unwrap().trim(), "= Hi");
+        assert!(convert("# Hi", "gfm", "docx").is_err());
+    }
+}
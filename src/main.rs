@@ -1,15 +1,16 @@
 use std::{fs, io};
-use std::io::Read;
+use std::fs::File;
+use std::io::{Read, Write};
 
-use clap::{Arg, ArgAction, Command};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+use clap::{value_parser, Arg, ArgAction, Command};
 use clap::builder::PossibleValuesParser;
 
-use md_converter::latex_writer::LatexWriter;
-use md_converter::maps::{ReaderMap, WriterMap};
-use md_converter::md_reader::MdReader;
-use md_converter::native_reader::NativeReader;
-use md_converter::native_writer::NativeWriter;
-use md_converter::typst_writer::TypstWriter;
+use md_converter::ast::Pandoc;
+use md_converter::maps::{ReaderMap, WriterMap, WriterOptions};
 
 fn main() {
     // let test = "hello        rust \\' \\ab";
@@ -22,13 +23,8 @@ fn main() {
 }
 
 fn run() {
-    let mut input_formats = ReaderMap::new();
-    input_formats.add("gfm", || MdReader);
-    input_formats.add("native", || NativeReader);
-    let mut output_formats = WriterMap::new();
-    output_formats.add("latex", LatexWriter::new);
-    output_formats.add("typst", TypstWriter::new);
-    output_formats.add("native", || NativeWriter);
+    let input_formats = ReaderMap::with_defaults();
+    let output_formats = WriterMap::with_defaults();
     let matches = Command::new("convert")
         .version("1.0")
         .author("Tymoteusz Malec, Jakub Szweda")
@@ -43,7 +39,7 @@ fn run() {
                 .help("Format of the input")
                 .required(true)
                 .action(ArgAction::Set)
-                .value_parser(PossibleValuesParser::new(input_formats.keys()))
+                .value_parser(PossibleValuesParser::new(input_formats.sorted_names()))
                 .value_name("INPUT_FORMAT")
                 .ignore_case(true),
         )
@@ -54,7 +50,7 @@ fn run() {
                 .help("Target format to convert to")
                 .required(true)
                 .action(ArgAction::Set)
-                .value_parser(PossibleValuesParser::new(output_formats.keys()))
+                .value_parser(PossibleValuesParser::new(output_formats.sorted_names()))
                 .value_name("OUTPUT_FORMAT")
                 .ignore_case(true),
         )
@@ -66,20 +62,63 @@ fn run() {
                 .value_name("OUTPUT_FILE")
                 .ignore_case(true),
         )
-        .arg(Arg::new("file").index(1).action(ArgAction::Set).value_name("FILE"))
+        .arg(
+            Arg::new("image-width")
+                .long("image-width")
+                .help("Width of images in the output, e.g. 80% or 0.8\\linewidth")
+                .action(ArgAction::Set)
+                .value_name("WIDTH"),
+        )
+        .arg(
+            Arg::new("toc")
+                .long("toc")
+                .help("Writes a table of contents before the content")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("shift-heading-level-by")
+                .long("shift-heading-level-by")
+                .help("Adds a number of levels to the level of every heading")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(usize))
+                .value_name("NUMBER"),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .help("Only checks if the input can be converted to the target format")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("file")
+                .help(
+                    "Input files, concatenated with a blank line between them or merged if they \
+                     are native documents",
+                )
+                .index(1)
+                .num_args(1..)
+                .action(ArgAction::Append)
+                .value_name("FILE"),
+        )
         .get_matches();
-    let content = match matches.get_one::<String>("file") {
-        Some(f) => match fs::read_to_string(f) {
-            Ok(s) => s,
-            Err(e) => {
-                println!("Failed to read file:\n{}", e);
-                return;
-            },
+    let contents = match matches.get_many::<String>("file") {
+        Some(files) => {
+            let mut contents = Vec::new();
+            for f in files {
+                match read_file(f) {
+                    Ok(s) => contents.push(s),
+                    Err(e) => {
+                        println!("Failed to read file {}:\n{}", f, e);
+                        return;
+                    },
+                }
+            }
+            contents
         },
         None => {
             let mut s = String::new();
             match io::stdin().read_to_string(&mut s) {
-                Ok(_) => s,
+                Ok(_) => vec![s],
                 Err(e) => {
                     println!("Failed to read input from stdin:\n{}", e);
                     return;
@@ -87,25 +126,66 @@ fn run() {
             }
         },
     };
-    let parsed = match input_formats.read(matches.get_one::<String>("from").unwrap(), &content) {
-        Ok(p) => p,
-        Err(e) => {
-            println!("Failed to parse input format:\n{}", e);
-            return;
-        },
+    let from = matches.get_one::<String>("from").unwrap();
+    // Json documents can't be joined as text, so each native file is read on its own and merged
+    let sources = if from == "native" { contents } else { vec![contents.join("\n\n")] };
+    let mut parsed = Pandoc::default();
+    for s in sources {
+        match input_formats.read(from, &s) {
+            Ok(p) => parsed.append(p),
+            Err(e) => {
+                println!("Failed to read input as {}:\n{}", from, e);
+                return;
+            },
+        }
+    }
+    let options = WriterOptions {
+        image_width: matches.get_one::<String>("image-width").cloned(),
+        toc: matches.get_flag("toc"),
+        header_offset: matches.get_one::<usize>("shift-heading-level-by").copied().unwrap_or(0),
     };
-    let result = match output_formats.write(matches.get_one::<String>("to").unwrap(), parsed) {
+    let to = matches.get_one::<String>("to").unwrap();
+    let result = output_formats.write_with_options(to, parsed, &options);
+    if matches.get_flag("check") {
+        match result {
+            Ok(_) => println!("Conversion to {} would succeed", to),
+            Err(e) => println!("Conversion to {} would fail:\n{}", to, e),
+        }
+        return;
+    }
+    let result = match result {
         Ok(s) => s,
         Err(e) => {
-            println!("Failed to parse output format:\n{}", e);
+            println!("Failed to write output as {}:\n{}", to, e);
             return;
         },
     };
     match matches.get_one::<String>("output") {
-        Some(f) => match fs::write(f, result) {
+        Some(f) => match write_file(f, &result) {
             Ok(_) => println!("Saved result to: {}", f),
             Err(e) => println!("Failed to save file:\n{}", e),
         },
         None => println!("{}", result),
     }
 }
+
+/// Reads a file, decompressing it if its name ends with `.gz`
+fn read_file(name: &str) -> io::Result<String> {
+    if !name.ends_with(".gz") {
+        return fs::read_to_string(name);
+    }
+    let mut result = String::new();
+    GzDecoder::new(File::open(name)?).read_to_string(&mut result)?;
+    Ok(result)
+}
+
+/// Writes a file, compressing it if its name ends with `.gz`
+fn write_file(name: &str, content: &str) -> io::Result<()> {
+    if !name.ends_with(".gz") {
+        return fs::write(name, content);
+    }
+    let mut encoder = GzEncoder::new(File::create(name)?, Compression::default());
+    encoder.write_all(content.as_bytes())?;
+    encoder.finish()?;
+    Ok(())
+}
@@ -3,8 +3,17 @@
 use std::collections::HashMap;
 use std::error::Error;
 
+use derive_more::Display;
+
 use crate::ast::Pandoc;
+use crate::latex_writer::LatexWriter;
+use crate::md_reader::MdReader;
+#[cfg(feature = "native")]
+use crate::native_reader::NativeReader;
+#[cfg(feature = "native")]
+use crate::native_writer::NativeWriter;
 use crate::traits::{AstReader, AstWriter};
+use crate::typst_writer::TypstWriter;
 
 /// Wrapper over an [`AstReader`] type that takes a function creating the reader and calls it,
 /// calls the read function and wraps an error into a boxed trait object
@@ -19,13 +28,25 @@ impl ReaderMap {
     #[must_use]
     pub fn new() -> Self { Self(HashMap::new()) }
 
-    /// Adds a new reader to the map from a function creating an instance of the reader
+    /// Creates a reader map with the readers available in the library: `gfm` and `native`
+    #[must_use]
+    pub fn with_defaults() -> Self {
+        let mut map = Self::new();
+        map.add("gfm", MdReader::new);
+        #[cfg(feature = "native")]
+        map.add("native", || NativeReader);
+        map
+    }
+
+    /// Adds a new reader to the map from a function creating an instance of the reader. Names are
+    /// case insensitive, a reader with the same name is replaced
     pub fn add<T, F>(&mut self, name: &'static str, reader_creator: F)
     where
         T: AstReader + 'static,
         T::ReadError: Error + 'static,
         F: Fn() -> T + 'static,
     {
+        self.0.retain(|k, _| !k.eq_ignore_ascii_case(name));
         self.0.insert(
             name,
             Box::new(move |s| match reader_creator().read(s) {
@@ -38,19 +59,35 @@ impl ReaderMap {
     /// Gets an iterator over the keys of the map
     pub fn keys(&self) -> impl Iterator<Item = &&'static str> { self.0.keys() }
 
-    /// Reads a string to a [`Pandoc`] ast with a given reader
+    /// Gets the names of the readers in alphabetical order
+    #[must_use]
+    pub fn sorted_names(&self) -> Vec<&'static str> { sorted_names(&self.0) }
+
+    /// Reads a string to a [`Pandoc`] ast with a given reader. The name is case insensitive
     /// # Errors
-    /// Returns an error received from a reader as a boxed trait object
-    /// # Panics
-    /// If key is not in map
+    /// Returns [`FormatNotFound`] if there is no reader with the given name or an error received
+    /// from a reader, both as a boxed trait object
     pub fn read(&self, name: &str, source: &str) -> Result<Pandoc, Box<dyn Error>> {
-        self.0.get(name).unwrap()(source)
+        get(&self.0, name).ok_or_else(|| FormatNotFound(name.to_owned()))?(source)
     }
 }
 
-/// Wrapper over an [`AstWriter`] type that takes a function creating the writer and calls it,
-/// calls the write function and wraps an error into a boxed trait object
-pub type Writer = Box<dyn Fn(Pandoc) -> Result<String, Box<dyn Error>>>;
+/// Wrapper over an [`AstWriter`] type that takes a function creating the writer from
+/// [`WriterOptions`] and calls it, calls the write function and wraps an error into a boxed trait
+/// object
+pub type Writer = Box<dyn Fn(Pandoc, &WriterOptions) -> Result<String, Box<dyn Error>>>;
+
+/// Options passed to the functions creating writers added with [`WriterMap::add_with_options`].
+/// Each writer applies the options it supports
+#[derive(Debug, Clone, Default)]
+pub struct WriterOptions {
+    /// Width of images, e.g. `80%`. Writers use their own default if it's not set
+    pub image_width: Option<String>,
+    /// Whether to write a table of contents before the content
+    pub toc: bool,
+    /// Number of levels added to the level of every header
+    pub header_offset: usize,
+}
 
 /// Container for holding writers
 #[derive(Default)]
@@ -61,6 +98,30 @@ impl WriterMap {
     #[must_use]
     pub fn new() -> Self { Self(HashMap::new()) }
 
+    /// Creates a writer map with the writers available in the library: `latex`, `typst` and
+    /// `native`. The LaTeX and Typst writers apply the [`WriterOptions`]
+    #[must_use]
+    pub fn with_defaults() -> Self {
+        let mut map = Self::new();
+        map.add_with_options("latex", |o: &WriterOptions| {
+            let mut builder = LatexWriter::builder().toc(o.toc).header_offset(o.header_offset);
+            if let Some(w) = &o.image_width {
+                builder = builder.image_width(w.clone());
+            }
+            builder.build()
+        });
+        map.add_with_options("typst", |o: &WriterOptions| {
+            let mut builder = TypstWriter::builder().toc(o.toc).header_offset(o.header_offset);
+            if let Some(w) = &o.image_width {
+                builder = builder.image_width(w.clone());
+            }
+            builder.build()
+        });
+        #[cfg(feature = "native")]
+        map.add("native", || NativeWriter);
+        map
+    }
+
     /// Adds a new writer to the map from a function creating an instance of the writer
     pub fn add<T, F>(&mut self, name: &'static str, writer_creator: F)
     where
@@ -68,9 +129,22 @@ impl WriterMap {
         T::WriteError: Error + 'static,
         F: Fn() -> T + 'static,
     {
+        self.add_with_options(name, move |_| writer_creator());
+    }
+
+    /// Adds a new writer to the map from a function creating an instance of the writer configured
+    /// with given [`WriterOptions`]. Names are case insensitive, a writer with the same name is
+    /// replaced
+    pub fn add_with_options<T, F>(&mut self, name: &'static str, writer_creator: F)
+    where
+        T: AstWriter + 'static,
+        T::WriteError: Error + 'static,
+        F: Fn(&WriterOptions) -> T + 'static,
+    {
+        self.0.retain(|k, _| !k.eq_ignore_ascii_case(name));
         self.0.insert(
             name,
-            Box::new(move |p| match writer_creator().write(p) {
+            Box::new(move |p, o| match writer_creator(o).write(p) {
                 Ok(s) => Ok(s),
                 Err(e) => Err(Box::new(e)),
             }),
@@ -80,12 +154,123 @@ impl WriterMap {
     /// Gets an iterator over the keys of the map
     pub fn keys(&self) -> impl Iterator<Item = &&'static str> { self.0.keys() }
 
-    /// Writes a [`Pandoc`] ast to a string with a given writer
+    /// Gets the names of the writers in alphabetical order
+    #[must_use]
+    pub fn sorted_names(&self) -> Vec<&'static str> { sorted_names(&self.0) }
+
+    /// Writes a [`Pandoc`] ast to a string with a given writer. The name is case insensitive
     /// # Errors
-    /// Returns an error received from a writer as a boxed trait object
-    /// # Panics
-    /// If key is not in map
+    /// Returns [`FormatNotFound`] if there is no writer with the given name or an error received
+    /// from a writer, both as a boxed trait object
     pub fn write(&self, name: &str, pandoc: Pandoc) -> Result<String, Box<dyn Error>> {
-        self.0.get(name).unwrap()(pandoc)
+        self.write_with_options(name, pandoc, &WriterOptions::default())
+    }
+
+    /// Writes a [`Pandoc`] ast to a string with a given writer configured with given
+    /// [`WriterOptions`]. The name is case insensitive
+    /// # Errors
+    /// Returns [`FormatNotFound`] if there is no writer with the given name or an error received
+    /// from a writer, both as a boxed trait object
+    pub fn write_with_options(
+        &self, name: &str, pandoc: Pandoc, options: &WriterOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        get(&self.0, name).ok_or_else(|| FormatNotFound(name.to_owned()))?(pandoc, options)
+    }
+}
+
+/// Error returned when there is no reader or writer with the given name in a map
+#[derive(Debug, Display)]
+#[display(fmt = "Format `{_0}` not found")]
+pub struct FormatNotFound(pub String);
+
+impl Error for FormatNotFound {}
+
+/// Gets the keys of a map in alphabetical order
+fn sorted_names<T>(map: &HashMap<&'static str, T>) -> Vec<&'static str> {
+    let mut names: Vec<_> = map.keys().copied().collect();
+    names.sort_unstable();
+    names
+}
+
+/// Gets a value from a map ignoring the case of the key
+fn get<'a, T>(map: &'a HashMap<&'static str, T>, name: &str) -> Option<&'a T> {
+    map.get(name)
+        .or_else(|| map.iter().find_map(|(k, v)| k.eq_ignore_ascii_case(name).then_some(v)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{attr_empty, Block, Inline};
+    use crate::md_reader::MdReader;
+    use crate::typst_writer::TypstWriter;
+
+    use super::*;
+
+    #[test]
+    fn writer_with_options() {
+        let mut map = WriterMap::new();
+        map.add_with_options("typst", |o: &WriterOptions| {
+            let mut builder = TypstWriter::builder();
+            if let Some(w) = &o.image_width {
+                builder = builder.image_width(w.clone());
+            }
+            builder.build()
+        });
+        let image = Inline::Image(attr_empty(), Vec::new(), (String::from("a.png"), String::new()));
+        let pandoc = Pandoc { blocks: vec![Block::Plain(vec![image])], ..Default::default() };
+        let options =
+            WriterOptions { image_width: Some(String::from("50%")), ..Default::default() };
+        let result = map.write_with_options("typst", pandoc.clone(), &options).unwrap();
+        assert_eq!(result, "#figure(image(\"a.png\", width: 50%))");
+        let result = map.write("typst", pandoc).unwrap();
+        assert_eq!(result, "#figure(image(\"a.png\", width: 100%))");
+    }
+
+    #[test]
+    fn case_insensitive_names() {
+        let mut readers = ReaderMap::new();
+        readers.add("gfm", MdReader::new);
+        let pandoc = readers.read("GFM", "text").unwrap();
+        assert_eq!(pandoc, readers.read("gfm", "text").unwrap());
+        let mut writers = WriterMap::new();
+        writers.add("Typst", TypstWriter::new);
+        writers.add("typst", TypstWriter::new);
+        assert_eq!(writers.keys().count(), 1);
+        assert_eq!(writers.write("TYPST", pandoc).unwrap().trim(), "text");
+    }
+
+    #[test]
+    fn format_not_found() {
+        let readers = ReaderMap::new();
+        let error = readers.read("gfm", "text").unwrap_err();
+        assert_eq!(error.downcast_ref::<FormatNotFound>().unwrap().0, "gfm");
+        let writers = WriterMap::new();
+        let error = writers.write("typst", Pandoc::default()).unwrap_err();
+        assert_eq!(error.to_string(), "Format `typst` not found");
+    }
+
+    #[test]
+    fn sorted_names() {
+        let mut writers = WriterMap::new();
+        writers.add("typst", TypstWriter::new);
+        writers.add("latex", crate::latex_writer::LatexWriter::new);
+        assert_eq!(writers.sorted_names(), ["latex", "typst"]);
+        let mut readers = ReaderMap::new();
+        readers.add("gfm", MdReader::new);
+        assert_eq!(readers.sorted_names(), ["gfm"]);
+    }
+
+    #[test]
+    fn with_defaults() {
+        #[cfg(feature = "native")]
+        {
+            assert_eq!(ReaderMap::with_defaults().sorted_names(), ["gfm", "native"]);
+            assert_eq!(WriterMap::with_defaults().sorted_names(), ["latex", "native", "typst"]);
+        }
+        #[cfg(not(feature = "native"))]
+        {
+            assert_eq!(ReaderMap::with_defaults().sorted_names(), ["gfm"]);
+            assert_eq!(WriterMap::with_defaults().sorted_names(), ["latex", "typst"]);
+        }
     }
 }
@@ -3,37 +3,390 @@
 use std::convert::Infallible;
 use std::iter;
 
+use derive_more::Display;
+
 pub use links::{Link, Links};
-use temp_block::TempBlock;
+use iters::Lines;
+use temp_block::{LineStarts, TempBlock};
 
-use crate::ast::Pandoc;
+use crate::ast::{Block, Pandoc};
 use crate::traits::AstReader;
 
+mod entities;
 pub mod inline_parser;
 mod iters;
 mod links;
 mod temp_block;
 
 /// Struct used for parsing GitHub Flavoured Markdown into the [`Pandoc`] type
-pub struct MdReader;
+#[derive(Default)]
+pub struct MdReader {
+    options: MdReaderOptions,
+    links: Links,
+}
 
-impl AstReader for MdReader {
-    type ReadError = Infallible;
+/// Options enabling extensions to GitHub Flavoured Markdown in a [`MdReader`]
+///
+/// The GitHub Flavoured Markdown extensions [`Self::strikethrough`], [`Self::tables`],
+/// [`Self::autolinks`] and [`Self::tasklists`] are enabled by default, all other options are
+/// disabled
+#[derive(Debug, Clone, Copy)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct MdReaderOptions {
+    /// Parse Pandoc style citations `[see @key, p. 5]` and `@key` into [`Inline::Cite`]
+    ///
+    /// [`Inline::Cite`]: crate::ast::Inline::Cite
+    pub citations: bool,
+    /// Parse `^text^` into [`Inline::Superscript`]
+    ///
+    /// [`Inline::Superscript`]: crate::ast::Inline::Superscript
+    pub superscript: bool,
+    /// Parse `~text~` into [`Inline::Subscript`] instead of [`Inline::Strikeout`]. Text between
+    /// double tildes is still parsed as [`Inline::Strikeout`]
+    ///
+    /// [`Inline::Subscript`]: crate::ast::Inline::Subscript
+    /// [`Inline::Strikeout`]: crate::ast::Inline::Strikeout
+    pub subscript: bool,
+    /// Parse `==text==` into an [`Inline::Span`] with the `highlight` class
+    ///
+    /// [`Inline::Span`]: crate::ast::Inline::Span
+    pub highlight: bool,
+    /// Parse text between double underscores `__text__` into [`Inline::Underline`] instead of
+    /// [`Inline::Strong`]. Double asterisks are still parsed as [`Inline::Strong`]
+    ///
+    /// [`Inline::Underline`]: crate::ast::Inline::Underline
+    /// [`Inline::Strong`]: crate::ast::Inline::Strong
+    pub underline: bool,
+    /// Parse text between tildes `~~text~~` into [`Inline::Strikeout`]. If disabled the `~` char
+    /// is always parsed as text, so this also disables [`Self::subscript`]
+    ///
+    /// [`Inline::Strikeout`]: crate::ast::Inline::Strikeout
+    pub strikethrough: bool,
+    /// Parse pipe tables into [`Block::Table`]
+    ///
+    /// [`Block::Table`]: crate::ast::Block::Table
+    pub tables: bool,
+    /// Parse bare urls `www.example.com`, `https://example.com` and email addresses into
+    /// [`Inline::Link`]
+    ///
+    /// [`Inline::Link`]: crate::ast::Inline::Link
+    pub autolinks: bool,
+    /// Parse list items starting with `[ ]` or `[x]` followed by whitespace into task list items
+    /// starting with a `☐` or `☒` char
+    pub tasklists: bool,
+    /// Report table rows with a cell count different from the table header as
+    /// [`Warning::RaggedTableRow`] in [`MdReader::read_with_warnings`]
+    pub strict_tables: bool,
+    /// Add the line on which a top level block starts as a `data-line` key-value pair to the
+    /// [`Attr`] of the block. Blocks without an [`Attr`] are left unchanged
+    ///
+    /// [`Attr`]: crate::ast::Attr
+    pub line_numbers: bool,
+    /// Parse Pandoc style definition lists, a single line term directly followed by lines starting
+    /// with `:` each beginning a definition, into [`Block::DefinitionList`]
+    pub definition_lists: bool,
+    /// Convert GitHub alerts, block quotes starting with a line containing only `[!NOTE]`,
+    /// `[!TIP]`, `[!IMPORTANT]`, `[!WARNING]` or `[!CAUTION]`, into a [`Block::Div`] with the
+    /// lowercase alert type as a class. The first element of the div is a div with the `title`
+    /// class containing the alert type
+    ///
+    /// [`Block::Div`]: crate::ast::Block::Div
+    pub alerts: bool,
+    /// Parse TeX math between single dollars into [`MathType::InlineMath`] and between double
+    /// dollars into [`MathType::DisplayMath`]. An opening single dollar has to be followed by a
+    /// non-whitespace char and a closing one has to follow a non-whitespace char and cannot be
+    /// followed by a digit. Escaped dollars `\$` never open or close math
+    ///
+    /// [`MathType::InlineMath`]: crate::ast::MathType::InlineMath
+    /// [`MathType::DisplayMath`]: crate::ast::MathType::DisplayMath
+    pub math: bool,
+    /// How raw HTML tags, processing instructions and declarations are read, both inline and as
+    /// HTML blocks. Comments are handled by [`Self::keep_comments`]
+    pub html: HtmlMode,
+    /// Keep HTML comments `<!-- ... -->` as [`Block::RawBlock`] and [`Inline::RawInline`] elements
+    /// with the `html` format. By default comments are dropped, regardless of [`Self::html`]
+    ///
+    /// [`Block::RawBlock`]: crate::ast::Block::RawBlock
+    /// [`Inline::RawInline`]: crate::ast::Inline::RawInline
+    pub keep_comments: bool,
+    /// Parse only the block structure leaving the content of each line as a single
+    /// [`Inline::Str`], with lines separated by [`Inline::SoftBreak`]. Faster for documents where
+    /// only the outline is needed
+    ///
+    /// [`Inline::Str`]: crate::ast::Inline::Str
+    /// [`Inline::SoftBreak`]: crate::ast::Inline::SoftBreak
+    pub block_only: bool,
+}
 
-    fn read(self, source: &str) -> Result<Pandoc, Self::ReadError> {
+impl Default for MdReaderOptions {
+    fn default() -> Self {
+        Self {
+            citations: false,
+            superscript: false,
+            subscript: false,
+            highlight: false,
+            underline: false,
+            strikethrough: true,
+            tables: true,
+            autolinks: true,
+            tasklists: true,
+            strict_tables: false,
+            line_numbers: false,
+            definition_lists: false,
+            alerts: false,
+            math: false,
+            html: HtmlMode::default(),
+            keep_comments: false,
+            block_only: false,
+        }
+    }
+}
+
+/// Ways of reading raw HTML in a [`MdReader`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HtmlMode {
+    /// Keep HTML as [`Inline::RawInline`] and [`Block::RawBlock`] elements with the `html` format
+    ///
+    /// [`Inline::RawInline`]: crate::ast::Inline::RawInline
+    /// [`Block::RawBlock`]: crate::ast::Block::RawBlock
+    Raw,
+    /// Read HTML as literal text. HTML blocks other than comments are not recognized and their
+    /// lines are read like any other text
+    #[default]
+    Escape,
+    /// Remove HTML leaving only the text between tags, HTML blocks are dropped entirely
+    Strip,
+}
+
+/// Non-fatal problem found while reading a document
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum Warning {
+    /// Table row with a cell count different from the table header. Excess cells are ignored and
+    /// missing cells are added as empty
+    #[display(fmt = "Table row `{row}` has {found} cells, expected {expected}")]
+    RaggedTableRow {
+        /// Content of the row
+        row: String,
+        /// Cell count of the table header
+        expected: usize,
+        /// Cell count of the row
+        found: usize,
+    },
+    /// Shortcut reference `[label]` without a matching link reference definition. It is read as
+    /// literal text
+    #[display(fmt = "Reference `[{_0}]` has no matching link reference definition")]
+    UnresolvedReference(String),
+    /// Link reference definition which isn't referenced anywhere in the document
+    #[display(fmt = "Link reference definition `[{_0}]` is never used")]
+    UnusedDefinition(String),
+}
+
+impl MdReader {
+    /// Creates a new [`MdReader`] with default [`MdReaderOptions`]
+    #[must_use]
+    pub fn new() -> Self { Self::default() }
+
+    /// Creates a new [`MdReader`] with given [`MdReaderOptions`]
+    #[must_use]
+    pub fn with_options(options: MdReaderOptions) -> Self { Self { options, ..Self::default() } }
+
+    /// Sets link reference definitions available in addition to the ones in the document. The
+    /// definitions from the document take precedence and unused predefined links aren't reported
+    /// as [`Warning::UnusedDefinition`]
+    #[must_use]
+    pub fn with_links(mut self, links: Links) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::citations`]
+    #[must_use]
+    pub const fn with_citations(mut self, value: bool) -> Self {
+        self.options.citations = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::superscript`]
+    #[must_use]
+    pub const fn with_superscript(mut self, value: bool) -> Self {
+        self.options.superscript = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::subscript`]
+    #[must_use]
+    pub const fn with_subscript(mut self, value: bool) -> Self {
+        self.options.subscript = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::highlight`]
+    #[must_use]
+    pub const fn with_highlight(mut self, value: bool) -> Self {
+        self.options.highlight = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::underline`]
+    #[must_use]
+    pub const fn with_underline(mut self, value: bool) -> Self {
+        self.options.underline = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::strikethrough`]
+    #[must_use]
+    pub const fn with_strikethrough(mut self, value: bool) -> Self {
+        self.options.strikethrough = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::tables`]
+    #[must_use]
+    pub const fn with_tables(mut self, value: bool) -> Self {
+        self.options.tables = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::autolinks`]
+    #[must_use]
+    pub const fn with_autolinks(mut self, value: bool) -> Self {
+        self.options.autolinks = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::tasklists`]
+    #[must_use]
+    pub const fn with_tasklists(mut self, value: bool) -> Self {
+        self.options.tasklists = value;
+        self
+    }
+
+    /// Parses strict `CommonMark` if `value` is true by disabling all the GitHub Flavoured
+    /// Markdown extensions: [`MdReaderOptions::strikethrough`], [`MdReaderOptions::tables`],
+    /// [`MdReaderOptions::autolinks`] and [`MdReaderOptions::tasklists`]. Otherwise enables all of
+    /// them. Extensions can be changed again by their own setters afterwards
+    #[must_use]
+    pub const fn with_strict(mut self, value: bool) -> Self {
+        self.options.strikethrough = !value;
+        self.options.tables = !value;
+        self.options.autolinks = !value;
+        self.options.tasklists = !value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::strict_tables`]
+    #[must_use]
+    pub const fn with_strict_tables(mut self, value: bool) -> Self {
+        self.options.strict_tables = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::line_numbers`]
+    #[must_use]
+    pub const fn with_line_numbers(mut self, value: bool) -> Self {
+        self.options.line_numbers = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::definition_lists`]
+    #[must_use]
+    pub const fn with_definition_lists(mut self, value: bool) -> Self {
+        self.options.definition_lists = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::alerts`]
+    #[must_use]
+    pub const fn with_alerts(mut self, value: bool) -> Self {
+        self.options.alerts = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::math`]
+    #[must_use]
+    pub const fn with_math(mut self, value: bool) -> Self {
+        self.options.math = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::block_only`]
+    #[must_use]
+    pub const fn with_block_only(mut self, value: bool) -> Self {
+        self.options.block_only = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::html`]
+    #[must_use]
+    pub const fn with_html(mut self, value: HtmlMode) -> Self {
+        self.options.html = value;
+        self
+    }
+
+    /// Sets [`MdReaderOptions::keep_comments`]
+    #[must_use]
+    pub const fn with_keep_comments(mut self, value: bool) -> Self {
+        self.options.keep_comments = value;
+        self
+    }
+
+    /// Reads a document like [`AstReader::read`] returning also the non-fatal problems found
+    #[must_use]
+    pub fn read_with_warnings(self, source: &str) -> (Pandoc, Vec<Warning>) {
+        let source = source.strip_prefix('\u{FEFF}').unwrap_or(source);
         let mut current = TempBlock::default();
         let mut finished = Vec::new();
+        let mut starts = LineStarts::default();
         let mut links = Links::new();
-        for line in source.lines() {
-            current.next_str(line, &mut finished, &mut links);
+        for line in Lines::new(source) {
+            current.next_str(line, &mut finished, &mut starts, &mut links, self.options);
         }
         current.finish_links(&mut links);
-        let result = finished
-            .into_iter()
-            .chain(iter::once(current))
-            .filter_map(|t| t.finish(&links))
-            .collect();
-        Ok(Pandoc { blocks: result, ..Default::default() })
+        links.merge(self.links);
+        let mut warnings = Vec::new();
+        let result = TempBlock::merge_definitions(
+            finished
+                .into_iter()
+                .chain(iter::once(current))
+                .zip(starts.finished.into_iter().chain(iter::once(starts.current)))
+                .filter_map(|(t, line)| {
+                    let block = t.finish(&links, self.options, &mut warnings);
+                    let unresolved = links.take_unresolved().into_iter();
+                    warnings.extend(unresolved.map(Warning::UnresolvedReference));
+                    let block = block?;
+                    let numbered = self.options.line_numbers;
+                    Some(if numbered { Self::add_line(block, line) } else { block })
+                }),
+        );
+        if !self.options.block_only {
+            let unused = links.unused().into_iter().map(str::to_owned);
+            warnings.extend(unused.map(Warning::UnusedDefinition));
+        }
+        (Pandoc { blocks: result, ..Default::default() }, warnings)
+    }
+
+    /// Adds the `data-line` key-value pair to the [`Attr`] of a block if it has one
+    ///
+    /// [`Attr`]: crate::ast::Attr
+    fn add_line(mut block: Block, line: usize) -> Block {
+        if let Block::CodeBlock(attr, _)
+        | Block::Header(_, attr, _)
+        | Block::Table(attr, ..)
+        | Block::Figure(attr, ..)
+        | Block::Div(attr, _) = &mut block
+        {
+            attr.2.push((String::from("data-line"), line.to_string()));
+        }
+        block
+    }
+}
+
+impl AstReader for MdReader {
+    type ReadError = Infallible;
+
+    fn read(self, source: &str) -> Result<Pandoc, Self::ReadError> {
+        Ok(self.read_with_warnings(source).0)
     }
 }
 
@@ -69,7 +422,7 @@ mod tests {
                 std::str::from_utf8(&child.wait_with_output().unwrap().stdout).unwrap(),
             )
             .unwrap();
-            let result = MdReader.read(e).unwrap();
+            let result = MdReader::new().read(e).unwrap();
             if result.blocks == expected.blocks {
                 println!("\n\x1b[32mExample {number} : success");
                 println!("Input:\n{e}");
@@ -136,3 +489,447 @@ mod tests {
     #[test]
     fn emph_singular() { test(345, 346) }
 }
+
+#[cfg(test)]
+mod fuzz {
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Runs of chars with special meaning in GitHub Flavoured Markdown and its extensions mixed
+    /// with multibyte chars, as well as fragments starting alerts, definitions and HTML
+    fn input() -> BoxedStrategy<String> {
+        let fragment = prop_oneof![
+            4 => r#"[ab12 \t\n*_~`#>\-+=|:.)(\[\]!<\\&;"'^$@ą€\x{2028}😀]{1,4}"#,
+            1 => prop_oneof![
+                Just("> [!NOTE]\n"),
+                Just("\n: "),
+                Just("[@key, p. 5]"),
+                Just("$$"),
+                Just("<div>"),
+                Just("</span>"),
+                Just("<!-- "),
+                Just(" -->"),
+                Just("<?x ?>"),
+                Just("&amp;"),
+            ]
+            .prop_map(String::from),
+        ];
+        vec(fragment, 0..24).prop_map(|f| f.concat()).boxed()
+    }
+
+    fn options() -> BoxedStrategy<MdReaderOptions> {
+        let html = prop_oneof![Just(HtmlMode::Raw), Just(HtmlMode::Escape), Just(HtmlMode::Strip)];
+        (proptest::array::uniform16(any::<bool>()), html)
+            .prop_map(|(b, html)| MdReaderOptions {
+                citations: b[0],
+                superscript: b[1],
+                subscript: b[2],
+                highlight: b[3],
+                underline: b[4],
+                strikethrough: b[5],
+                tables: b[6],
+                autolinks: b[7],
+                tasklists: b[8],
+                strict_tables: b[9],
+                line_numbers: b[10],
+                definition_lists: b[11],
+                alerts: b[12],
+                math: b[13],
+                html,
+                keep_comments: b[14],
+                block_only: b[15],
+            })
+            .boxed()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn random_input_does_not_panic(source in input(), options in options()) {
+            let _ = MdReader::with_options(options).read(&source);
+        }
+    }
+}
+
+#[cfg(test)]
+mod read_tests {
+    use crate::ast::{attr_empty, Block, Format, Inline, ListNumberDelim, ListNumberStyle};
+
+    use super::*;
+
+    #[test]
+    fn toggles_change_output() {
+        let source = "H~2~O";
+        let default = MdReader::new().read(source).unwrap();
+        let Block::Para(inlines) = &default.blocks[0] else { panic!() };
+        assert!(matches!(inlines[1], Inline::Strikeout(_)));
+        let subscript = MdReader::new().with_subscript(true).read(source).unwrap();
+        let Block::Para(inlines) = &subscript.blocks[0] else { panic!() };
+        assert!(matches!(inlines[1], Inline::Subscript(_)));
+        let disabled = MdReader::new().with_subscript(true).with_subscript(false).read(source);
+        assert_eq!(disabled.unwrap(), default);
+    }
+
+    #[test]
+    fn carriage_return_line_endings() {
+        let result = MdReader::new().read("# Heading\rparagraph\r\r- item\r\n").unwrap();
+        assert_eq!(result.blocks.len(), 3);
+        assert!(matches!(result.blocks[0], Block::Header(1, ..)));
+        assert!(matches!(result.blocks[1], Block::Para(_)));
+        assert!(matches!(result.blocks[2], Block::BulletList(_)));
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        let para = |source: &str| MdReader::new().read(source).unwrap().blocks;
+        let str = |s: &str| Inline::Str(String::from(s));
+        let hard = vec![Block::Para(vec![str("a"), Inline::LineBreak, str("b")])];
+        let soft = vec![Block::Para(vec![str("a"), Inline::SoftBreak, str("b")])];
+        assert_eq!(para("a  \nb"), hard);
+        assert_eq!(para("a   \nb"), hard);
+        assert_eq!(para("a \nb"), soft);
+        assert_eq!(para("x\na  \nb  "), vec![Block::Para(vec![
+            str("x"),
+            Inline::SoftBreak,
+            str("a"),
+            Inline::LineBreak,
+            str("b"),
+        ])]);
+        assert_eq!(para("a  "), vec![Block::Para(vec![str("a")])]);
+    }
+
+    fn link_url(source: &str) -> String {
+        let result = MdReader::new().read(source).unwrap();
+        let Some(Block::Para(inlines)) = result.blocks.last() else { panic!() };
+        let [Inline::Link(_, _, (url, _))] = inlines.as_slice() else { panic!() };
+        url.clone()
+    }
+
+    #[test]
+    fn link_definitions_in_containers() {
+        assert_eq!(link_url("> [foo]: /url\n\n[foo]"), "/url");
+        assert_eq!(link_url("> [foo]: /url\n>\n> text\n\n[foo]"), "/url");
+        assert_eq!(link_url("> - [foo]: /url\n\n[foo]"), "/url");
+        assert_eq!(link_url("- a\n\n  [foo]: /url\n- b\n\n[foo]"), "/url");
+    }
+
+    #[test]
+    fn first_link_definition_wins() {
+        assert_eq!(link_url("[foo]: /first\n\n- [foo]: /second\n\n[foo]"), "/first");
+        assert_eq!(link_url("[foo]: /first\n- [foo]: /second\n\n[foo]"), "/first");
+        assert_eq!(link_url("- [foo]: /first\n- [foo]: /second\n\n[foo]"), "/first");
+        assert_eq!(link_url("- > [foo]: /first\n\n[foo]: /second\n\n[foo]"), "/first");
+        assert_eq!(link_url("> [foo]: /first\n> [FOO]: /second\n\n[Foo]"), "/first");
+        assert_eq!(link_url("[foo]: /a\\*b&amp;c\n\n[foo]"), "/a*b&c");
+    }
+
+    #[test]
+    fn strict_mode() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let strict = || MdReader::new().with_strict(true);
+        let result = strict().read("~~x~~ ~y~").unwrap();
+        assert_eq!(result.blocks, vec![Block::Para(vec![str("~~x~~"), Inline::Space, str("~y~")])]);
+        let result = strict().read("| a |\n| - |\n| b |").unwrap();
+        assert!(matches!(result.blocks.as_slice(), [Block::Para(_)]));
+        let result = strict().read("www.example.com").unwrap();
+        assert_eq!(result.blocks, vec![Block::Para(vec![str("www.example.com")])]);
+        let result = strict().read("- [x] a").unwrap();
+        let item = vec![Block::Plain(vec![str("[x]"), Inline::Space, str("a")])];
+        assert_eq!(result.blocks, vec![Block::BulletList(vec![item])]);
+        let result = strict().with_tables(true).read("| a |\n| - |\n| b |").unwrap();
+        assert!(matches!(result.blocks.as_slice(), [Block::Table(..)]));
+        let result = MdReader::new().read("~~x~~").unwrap();
+        assert_eq!(result.blocks, vec![Block::Para(vec![Inline::Strikeout(vec![str("x")])])]);
+    }
+
+    #[test]
+    fn disabled_extensions() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let result = MdReader::new().with_strikethrough(false).read("~~x~~").unwrap();
+        assert_eq!(result.blocks, vec![Block::Para(vec![str("~~x~~")])]);
+        let table = "| a |\n| - |\n| b |";
+        let result = MdReader::new().with_tables(false).read(table).unwrap();
+        assert!(matches!(result.blocks.as_slice(), [Block::Para(_)]));
+        let result = MdReader::new().read(table).unwrap();
+        assert!(matches!(result.blocks.as_slice(), [Block::Table(..)]));
+        let result = MdReader::new().with_autolinks(false).read("www.example.com").unwrap();
+        assert_eq!(result.blocks, vec![Block::Para(vec![str("www.example.com")])]);
+    }
+
+    #[test]
+    fn task_lists() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let item = |s: &str| vec![Block::Plain(vec![str(s), Inline::Space, str("a")])];
+        let source = "- [ ] a\n- [x] a\n- [X]\n- [x]a";
+        let result = MdReader::new().read(source).unwrap();
+        assert_eq!(result.blocks, vec![Block::BulletList(vec![
+            item("☐"),
+            item("☒"),
+            vec![Block::Plain(vec![str("[X]")])],
+            vec![Block::Plain(vec![str("[x]a")])],
+        ])]);
+        let result = MdReader::new().with_tasklists(false).read("- [ ] a").unwrap();
+        let unchecked = vec![str("["), Inline::Space, str("]"), Inline::Space, str("a")];
+        assert_eq!(result.blocks, vec![Block::BulletList(vec![vec![Block::Plain(unchecked)]])]);
+    }
+
+    #[test]
+    fn ragged_table_warnings() {
+        let source = "| a | b |\n| - | - |\n| 1 |\n| 1 | 2 | 3 |\n| 1 | 2 |";
+        let (default, warnings) = MdReader::new().read_with_warnings(source);
+        assert!(warnings.is_empty());
+        let reader = MdReader::new().with_strict_tables(true);
+        let (strict, warnings) = reader.read_with_warnings(source);
+        assert_eq!(strict, default);
+        assert_eq!(warnings, vec![
+            Warning::RaggedTableRow { row: String::from("| 1 |"), expected: 2, found: 1 },
+            Warning::RaggedTableRow { row: String::from("| 1 | 2 | 3 |"), expected: 2, found: 3 },
+        ]);
+        assert_eq!(warnings[0].to_string(), "Table row `| 1 |` has 1 cells, expected 2");
+    }
+
+    #[test]
+    fn unresolved_reference_warnings() {
+        let source = "[used] [ref]\n\n| [cell] |\n| - |\n\n[ ] [x](/url)\n\n[used]: /url";
+        let (result, warnings) = MdReader::new().read_with_warnings(source);
+        assert_eq!(warnings, vec![
+            Warning::UnresolvedReference(String::from("ref")),
+            Warning::UnresolvedReference(String::from("cell")),
+        ]);
+        let message = "Reference `[ref]` has no matching link reference definition";
+        assert_eq!(warnings[0].to_string(), message);
+        assert_eq!(result.blocks.len(), 3);
+        let (_, warnings) = MdReader::new().with_citations(true).read_with_warnings("[@key]");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn unused_definition_warnings() {
+        let source = "[Used]\n\n[used]: /url\n[unused]: /url\n\n[Used]: /other";
+        let (_, warnings) = MdReader::new().read_with_warnings(source);
+        assert_eq!(warnings, vec![Warning::UnusedDefinition(String::from("unused"))]);
+        assert_eq!(warnings[0].to_string(), "Link reference definition `[unused]` is never used");
+        let (_, warnings) = MdReader::new().with_block_only(true).read_with_warnings(source);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn predefined_links() {
+        let mut links = Links::new();
+        links.add_new("shared", "/shared", None);
+        links.add_new("Local", "/shared", None);
+        links.add_new("unused", "/shared", None);
+        let source = "[shared] [local]\n\n[local]: /local";
+        let (result, warnings) = MdReader::new().with_links(links).read_with_warnings(source);
+        let link = |url: &str, title: &str| {
+            Inline::Link(attr_empty(), Vec::new(), (url.to_owned(), title.to_owned()))
+        };
+        assert_eq!(result.blocks, vec![Block::Para(vec![
+            link("/shared", "shared"),
+            Inline::Space,
+            link("/local", "local"),
+        ])]);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn html_comment_blocks() {
+        let source = "text\n<!-- a\n\nb -->\n<!-- c -->\n\n> <!-->";
+        let raw = |s: &str| Block::RawBlock(Format(String::from("html")), String::from(s));
+        let text = Block::Para(vec![Inline::Str(String::from("text"))]);
+        let result = MdReader::new().read(source).unwrap();
+        assert_eq!(result.blocks, vec![text.clone(), Block::BlockQuote(Vec::new())]);
+        let result = MdReader::new().with_keep_comments(true).read(source).unwrap();
+        assert_eq!(result.blocks, vec![
+            text,
+            raw("<!-- a\n\nb -->"),
+            raw("<!-- c -->"),
+            Block::BlockQuote(vec![raw("<!-->")]),
+        ]);
+    }
+
+    #[test]
+    fn html_comment_followed_by_text() {
+        let source = "<!-- a --> Important text\n\n<!-- b\nc --> after\nmore";
+        let str = |s: &str| Inline::Str(String::from(s));
+        let raw = |s: &str| Block::RawBlock(Format(String::from("html")), String::from(s));
+        let comment = Inline::RawInline(Format(String::from("html")), String::from("<!-- a -->"));
+        let after = Block::Para(vec![str("after"), Inline::SoftBreak, str("more")]);
+        let result = MdReader::new().read(source).unwrap();
+        assert_eq!(result.blocks, vec![
+            Block::Para(vec![str("Important"), Inline::Space, str("text")]),
+            after.clone(),
+        ]);
+        let result = MdReader::new().with_keep_comments(true).read(source).unwrap();
+        assert_eq!(result.blocks, vec![
+            Block::Para(vec![comment, Inline::Space, str("Important"), Inline::Space, str("text")]),
+            raw("<!-- b\nc -->"),
+            after,
+        ]);
+    }
+
+    #[test]
+    fn html_blocks_modes() {
+        let source = "<div class=\"a\">\n*b*\n\ntext <i>c</i>\n<pre>\n\n</pre>";
+        let text = |s: &str| Inline::Str(String::from(s));
+        let raw = |s: &str| Block::RawBlock(Format(String::from("html")), String::from(s));
+        let tag = |s: &str| Inline::RawInline(Format(String::from("html")), String::from(s));
+        let read = |mode| MdReader::new().with_html(mode).read(source).unwrap().blocks;
+        assert_eq!(read(HtmlMode::Raw), vec![
+            raw("<div class=\"a\">\n*b*"),
+            Block::Para(vec![text("text"), Inline::Space, tag("<i>"), text("c"), tag("</i>")]),
+            raw("<pre>\n\n</pre>"),
+        ]);
+        assert_eq!(read(HtmlMode::Strip), vec![Block::Para(vec![
+            text("text"),
+            Inline::Space,
+            text("c"),
+        ])]);
+        assert_eq!(read(HtmlMode::Escape), MdReader::new().read(source).unwrap().blocks);
+    }
+
+    #[test]
+    fn html_blocks_escaped_by_default() {
+        let text = |s: &str| Inline::Str(String::from(s));
+        let result = MdReader::new().read("a\n<div>\nb\n\n<pre>\nx\n\ny\n</pre>").unwrap();
+        assert_eq!(result.blocks, vec![
+            Block::Para(vec![
+                text("a"),
+                Inline::SoftBreak,
+                text("<div>"),
+                Inline::SoftBreak,
+                text("b"),
+            ]),
+            Block::Para(vec![text("<pre>"), Inline::SoftBreak, text("x")]),
+            Block::Para(vec![text("y"), Inline::SoftBreak, text("</pre>")]),
+        ]);
+    }
+
+    #[test]
+    fn bom_is_stripped() {
+        let result = MdReader::new().read("\u{FEFF}# Heading").unwrap();
+        let expected = Block::Header(1, attr_empty(), vec![Inline::Str(String::from("Heading"))]);
+        assert_eq!(result.blocks, vec![expected]);
+    }
+
+    #[test]
+    fn line_numbers() {
+        let source = "text\n\n# Heading\n\n> quote\n```\ncode\n```\n| a |\n| - |";
+        let line = |b: &Block| match b {
+            Block::Header(_, attr, _) | Block::CodeBlock(attr, _) | Block::Table(attr, ..) =>
+                attr.2.clone(),
+            _ => Vec::new(),
+        };
+        let data_line = |l: &str| vec![(String::from("data-line"), String::from(l))];
+        let result = MdReader::new().with_line_numbers(true).read(source).unwrap();
+        let lines: Vec<_> = result.blocks.iter().map(line).collect();
+        assert_eq!(lines, [Vec::new(), data_line("3"), Vec::new(), data_line("6"), data_line("9")]);
+        let result = MdReader::new().read(source).unwrap();
+        assert!(result.blocks.iter().all(|b| line(b).is_empty()));
+        let source = "a\n\nTitle\n===\nb\nc\n| a |\n| - |";
+        let result = MdReader::new().with_line_numbers(true).read(source).unwrap();
+        let lines: Vec<_> = result.blocks.iter().map(line).collect();
+        assert_eq!(lines, [Vec::new(), data_line("3"), Vec::new(), data_line("7")]);
+    }
+
+    #[test]
+    fn nested_mixed_lists() {
+        let source = "- a\n  1. b\n     * c\n       1) d\n  2. e\n- f";
+        let plain = |s: &str| Block::Plain(vec![Inline::Str(String::from(s))]);
+        let ordered =
+            |delim, items| Block::OrderedList((1, ListNumberStyle::Decimal, delim), items);
+        let innermost = ordered(ListNumberDelim::OneParen, vec![vec![plain("d")]]);
+        let inner = Block::BulletList(vec![vec![plain("c"), innermost]]);
+        let items = vec![vec![plain("b"), inner], vec![plain("e")]];
+        let middle = ordered(ListNumberDelim::Period, items);
+        let expected = vec![Block::BulletList(vec![vec![plain("a"), middle], vec![plain("f")]])];
+        assert_eq!(MdReader::new().read(source).unwrap().blocks, expected);
+    }
+
+    #[test]
+    fn list_ending_with_blank_line() {
+        let is_tight = |source: &str| {
+            let result = MdReader::new().read(source).unwrap();
+            let Block::BulletList(items) = &result.blocks[0] else { panic!() };
+            items.iter().flatten().all(|b| !matches!(b, Block::Para(_)))
+        };
+        assert!(is_tight("- a\n- b\n\n"));
+        assert!(is_tight("- a\n- b\n\n\nc"));
+        assert!(is_tight("- a\n  - b\n\n\nc"));
+        assert!(!is_tight("- a\n- b\n\n- c\n\n"));
+        assert!(!is_tight("- a\n- b\n\n  c\n\n"));
+    }
+
+    #[test]
+    fn ordered_list_after_paragraph() {
+        let blocks = |source: &str| MdReader::new().read(source).unwrap().blocks;
+        assert!(matches!(blocks("a\n1. item")[..], [Block::Para(_), Block::OrderedList(..)]));
+        assert!(matches!(blocks("a\n1) item")[..], [Block::Para(_), Block::OrderedList(..)]));
+        assert!(matches!(blocks("a\n2. item")[..], [Block::Para(_)]));
+        assert!(matches!(blocks("a\n2) item")[..], [Block::Para(_)]));
+        assert!(matches!(blocks("a\n10. item")[..], [Block::Para(_)]));
+        assert!(matches!(blocks("a\n\n2. item")[..], [Block::Para(_), Block::OrderedList(..)]));
+    }
+
+    #[test]
+    fn definition_lists() {
+        let plain = |s: &str| vec![Block::Plain(vec![Inline::Str(String::from(s))])];
+        let term = |s: &str| vec![Inline::Str(String::from(s))];
+        let read = |source| MdReader::new().with_definition_lists(true).read(source).unwrap();
+        let result = read("Term\n: first\n:   second");
+        let definitions = vec![plain("first"), plain("second")];
+        let expected = Block::DefinitionList(vec![(term("Term"), definitions)]);
+        assert_eq!(result.blocks, [expected]);
+        let result = read("A\n: a\n\nB\n: b\n\n> C\n> : c");
+        let [Block::DefinitionList(items), Block::BlockQuote(quote)] = &result.blocks[..] else {
+            panic!()
+        };
+        assert_eq!(items.len(), 2);
+        assert!(matches!(quote[..], [Block::DefinitionList(_)]));
+        let result = MdReader::new().read("Term\n: first").unwrap();
+        assert!(matches!(result.blocks[..], [Block::Para(_)]));
+        assert!(matches!(read("Two\nlines\n: first").blocks[..], [Block::Para(_)]));
+    }
+
+    #[test]
+    fn empty_list_item_followed_by_blank_line() {
+        let text = || vec![Inline::Str(String::from("text"))];
+        let result = MdReader::new().read("-\n\n  text").unwrap();
+        assert_eq!(result.blocks, [Block::BulletList(vec![Vec::new()]), Block::Para(text())]);
+        let result = MdReader::new().read("-\n  text").unwrap();
+        assert_eq!(result.blocks, [Block::BulletList(vec![vec![Block::Plain(text())]])]);
+    }
+
+    #[test]
+    fn multi_line_code_span() {
+        let code = |s: &str| Inline::Code(attr_empty(), String::from(s));
+        let str = |s: &str| Inline::Str(String::from(s));
+        let result = MdReader::new().read("a `code\nspan` b").unwrap();
+        assert_eq!(result.blocks, [Block::Para(vec![
+            str("a"),
+            Inline::Space,
+            code("code span"),
+            Inline::Space,
+            str("b"),
+        ])]);
+        let result = MdReader::new().read("> `a\n>  b`").unwrap();
+        assert_eq!(result.blocks, [Block::BlockQuote(vec![Block::Para(vec![code("a b")])])]);
+        let result = MdReader::new().read("- `a\n  b`").unwrap();
+        assert_eq!(result.blocks, [Block::BulletList(vec![vec![Block::Plain(vec![code("a b")])]])]);
+    }
+
+    #[test]
+    fn block_only() {
+        let str = |s: &str| Inline::Str(String::from(s));
+        let result = MdReader::new().with_block_only(true).read("# *Title*\n\na **b**\nc").unwrap();
+        assert_eq!(result.blocks, [
+            Block::new_header(1, vec![str("*Title*")]),
+            Block::Para(vec![str("a **b**"), Inline::SoftBreak, str("c")]),
+        ]);
+        let result = MdReader::new().read("# *Title*").unwrap();
+        assert_eq!(result.blocks, [Block::new_header(1, vec![Inline::Emph(vec![str("Title")])])]);
+    }
+}
@@ -1,14 +1,17 @@
 use std::collections::HashMap;
-use std::fs;
 use std::iter::Peekable;
+use std::mem;
 use std::num::ParseIntError;
 use std::str::CharIndices;
 use std::string::String;
 
 use lazy_static::lazy_static;
 
-use crate::ast::{attr_empty, Inline};
+use crate::ast::{attr_empty, Citation, CitationMode, Format, Inline, MathType, NodeMut};
+use crate::md_reader::entities;
+use crate::md_reader::iters::Iter;
 use crate::md_reader::links::{Link, Links};
+use crate::md_reader::{HtmlMode, MdReaderOptions};
 
 /// Structure containing methods for passing inlines with the main method for this being
 /// [`InlineParser::parse_lines`]
@@ -56,11 +59,13 @@ struct BacktickString {
     start_index: usize,
 }
 
-/// Enum used for keeping the information on whether the slice of the base string is a Code or an
-/// Inline slice on which the latter parsing methods [`InlineParser::parse_inline_slice`] and
-/// [`InlineParser::parse_code_slice`] depend
+/// Enum used for keeping the information on whether the slice of the base string is a Code, Math
+/// or an Inline slice on which the latter parsing methods [`InlineParser::parse_inline_slice`],
+/// [`InlineParser::parse_code_slice`] and [`InlineParser::parse_math_slice`] depend
+#[allow(clippy::enum_variant_names)]
 enum SliceVariant<'a> {
     CodeSlice(&'a str),
+    MathSlice(&'a str),
     InlineSlice(&'a str),
 }
 
@@ -72,24 +77,18 @@ enum StringOrChar {
 }
 
 lazy_static! {
-    static ref ENTITIES: HashMap<String, String> = {
-        let vec: Vec<(String, String)> =
-            serde_json::from_str(&fs::read_to_string("entities.json").unwrap()).unwrap();
-        vec.into_iter().collect()
-    };
+    static ref ENTITIES: HashMap<&'static str, &'static str> =
+        entities::ENTITIES.into_iter().collect();
 }
 
 impl InlineParser {
-    const ASCII_PUNCTUATION: [char; 31] = [
+    const ASCII_PUNCTUATION: [char; 32] = [
         '!', '"', '#', '%', '&', '\'', '(', ')', '*', ',', '.', '/', ':', ';', '?', '@', '[', '\\',
-        ']', '^', '_', '`', '{', '}', '|', '~', '-', '$', '<', '>', '=',
-    ];
-    const UNICODE_WHITESPACE: [char; 25] = [
-        '\u{0009}', '\u{000A}', '\u{000B}', '\u{000C}', '\u{000D}', '\u{0020}', '\u{0085}',
-        '\u{00A0}', '\u{1680}', '\u{2000}', '\u{2001}', '\u{2002}', '\u{2003}', '\u{2004}',
-        '\u{2005}', '\u{2006}', '\u{2007}', '\u{2008}', '\u{2009}', '\u{200A}', '\u{2028}',
-        '\u{2029}', '\u{202F}', '\u{205F}', '\u{3000}',
+        ']', '^', '_', '`', '{', '}', '|', '~', '-', '$', '<', '>', '=', '+',
     ];
+    /// Format of the [`Inline::RawInline`] elements temporarily holding backslash escaped
+    /// superscript and highlight markers, see [`Self::handle_backslash`]
+    const ESCAPED_MARKER: &'static str = "escaped-marker";
 
     /// Method receives the base paragraph and returns potential backtick strings which is necessary
     /// for code span parsing in [`Self::parse_backtick_string_length_vector`]
@@ -191,6 +190,79 @@ impl InlineParser {
         Self::parse_backtick_string_length_vector(paragraph, &backticks)
     }
 
+    /// Splits math slices out of the inline slices, escaped chars are skipped so `\\$` stays a
+    /// literal dollar handled by [`Self::handle_backslash`]
+    fn parse_math_spans(slices: Vec<SliceVariant<'_>>) -> Vec<SliceVariant<'_>> {
+        let mut result = Vec::with_capacity(slices.len());
+        for slice in slices {
+            let SliceVariant::InlineSlice(text) = slice else {
+                result.push(slice);
+                continue;
+            };
+            let bytes = text.as_bytes();
+            let mut previous = 0;
+            let mut i = 0;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'\\' => i += 2,
+                    b'$' => match Self::math_length(&text[i..]) {
+                        Some(length) => {
+                            if previous < i {
+                                result.push(SliceVariant::InlineSlice(&text[previous..i]));
+                            }
+                            result.push(SliceVariant::MathSlice(&text[i..i + length]));
+                            i += length;
+                            previous = i;
+                        },
+                        None => i += if text[i..].starts_with("$$") { 2 } else { 1 },
+                    },
+                    _ => i += 1,
+                }
+            }
+            if previous < text.len() {
+                result.push(SliceVariant::InlineSlice(&text[previous..]));
+            }
+        }
+        result
+    }
+
+    /// Returns the length of the math span including dollars at the start of the text
+    fn math_length(text: &str) -> Option<usize> {
+        let display = text.starts_with("$$");
+        let delimiter = if display { "$$" } else { "$" };
+        let content = &text[delimiter.len()..];
+        if !display && content.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let bytes = content.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'$' if i > 0 && content[i..].starts_with(delimiter) => {
+                    let closes = display
+                        || !content[..i].ends_with(char::is_whitespace)
+                            && !content[i + 1..].starts_with(|c: char| c.is_ascii_digit());
+                    if closes {
+                        return Some(delimiter.len() * 2 + i);
+                    }
+                    i += 1;
+                },
+                _ => i += 1,
+            }
+        }
+        None
+    }
+
+    /// Parses a math slice including dollars into [`Inline::Math`]
+    fn parse_math_slice(slice: &str) -> InlineElement<'_> {
+        let element = slice.strip_prefix("$$").map_or_else(
+            || Inline::Math(MathType::InlineMath, slice[1..slice.len() - 1].to_owned()),
+            |content| Inline::Math(MathType::DisplayMath, content[..content.len() - 2].to_owned()),
+        );
+        InlineElement { slice, element }
+    }
+
     /// This function takes a text slice and proceeds to parse every html entity containing
     /// abbreviated char names for example &quot; will be parsed to "
     #[must_use]
@@ -206,7 +278,7 @@ impl InlineParser {
                     loop {
                         match temp_iter.next() {
                             Some(';') => {
-                                match ENTITIES.get(&current) {
+                                match ENTITIES.get(current.as_str()) {
                                     Some(c) => {
                                         new_paragraph.push_str(c);
                                     },
@@ -238,13 +310,40 @@ impl InlineParser {
         new_paragraph
     }
 
+    /// Unescapes a link destination or title: removes backslashes before ASCII punctuation and
+    /// replaces html entities outside of the escaped chars
+    #[must_use]
+    pub fn unescape(text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut run = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match chars.peek() {
+                Some(&next) if c == '\\' && Self::ASCII_PUNCTUATION.contains(&next) => {
+                    result.push_str(&Self::parse_html_entities(&mem::take(&mut run)));
+                    result.push(next);
+                    chars.next();
+                },
+                _ => run.push(c),
+            }
+        }
+        result.push_str(&Self::parse_html_entities(&run));
+        result
+    }
+
     /// This function iterates over the given paragraph and runs methods when it finds special
     /// characters having some functionality in GFM
     #[must_use]
-    pub fn parse_lines(paragraph: &str, links: &Links) -> Vec<Inline> {
+    pub fn parse_lines(paragraph: &str, links: &Links, options: MdReaderOptions) -> Vec<Inline> {
+        if options.block_only {
+            return Self::unparsed_lines(paragraph);
+        }
         // let new_paragraph = Self::parse_html_entities(paragraph);
         let new_paragraph = paragraph;
-        let inlines_and_code = Self::parse_code_spans(new_paragraph);
+        let mut inlines_and_code = Self::parse_code_spans(new_paragraph);
+        if options.math {
+            inlines_and_code = Self::parse_math_spans(inlines_and_code);
+        }
         let mut last_opener_star: [Option<usize>; 3] = [None; 3];
         let mut last_opener_floor: [Option<usize>; 3] = [None; 3];
         let mut result: Vec<InlineElement> = Vec::new();
@@ -260,10 +359,14 @@ impl InlineParser {
                     // println!("Code {x}");
                     is_beginning = false;
                 },
+                Some(&SliceVariant::MathSlice(x)) => {
+                    result.push(Self::parse_math_slice(x));
+                    is_beginning = false;
+                },
                 Some(&SliceVariant::InlineSlice(x)) => {
                     delimiter_stack.append(&mut Self::parse_inline_slice(
                         x, &mut result, &mut last_opener_star, &mut last_opener_floor,
-                        is_beginning, links,
+                        is_beginning, links, options,
                     ));
                     is_beginning = false;
                     // println!("Inline {x}");
@@ -274,7 +377,7 @@ impl InlineParser {
         let mut true_result: Vec<Inline> = vec![];
         let mut is_prev_str = false;
 
-        Self::parse_emph(new_paragraph, &mut delimiter_stack, 0, &mut result);
+        Self::parse_emph(new_paragraph, &mut delimiter_stack, 0, &mut result, options);
 
         for x in &result {
             match x.element.clone() {
@@ -304,9 +407,403 @@ impl InlineParser {
         //         print!("{:?} ", x);
         //     }
         // }
+        let mut true_result =
+            if options.citations { Self::parse_citations(true_result) } else { true_result };
+        if options.superscript {
+            true_result = Self::parse_marker_pairs(true_result, "^", false, &Inline::Superscript);
+        }
+        if options.highlight {
+            let class = vec![String::from("highlight")];
+            let highlight = |i| Inline::Span((String::new(), class.clone(), Vec::new()), i);
+            true_result = Self::parse_marker_pairs(true_result, "==", true, &highlight);
+        }
+        if options.superscript || options.highlight {
+            Inline::walk(&mut true_result, &mut |node| {
+                let NodeMut::Inline(i) = node else {
+                    return;
+                };
+                if let Inline::RawInline(Format(format), s) = i {
+                    if format == Self::ESCAPED_MARKER {
+                        *i = Inline::Str(mem::take(s));
+                    }
+                }
+            });
+        }
+        if options.autolinks {
+            true_result = Self::parse_autolinks(true_result);
+        }
+        Inline::normalize_inlines(&mut true_result);
+        // Dropped comments and HTML tags can leave spaces at the edges of the paragraph
+        if true_result.first() == Some(&Inline::Space) {
+            true_result.remove(0);
+        }
+        if true_result.last() == Some(&Inline::Space) {
+            true_result.pop();
+        }
         true_result
     }
 
+    /// Returns each non-empty line as an [`Inline::Str`] separated by [`Inline::SoftBreak`]
+    fn unparsed_lines(paragraph: &str) -> Vec<Inline> {
+        let mut result = Vec::new();
+        for line in paragraph.lines().filter(|l| !l.is_empty()) {
+            if !result.is_empty() {
+                result.push(Inline::SoftBreak);
+            }
+            result.push(Inline::Str(line.to_owned()));
+        }
+        result
+    }
+
+    /// Splits [`Inline::Str`] elements (also nested in emphasis) into text and [`Inline::Link`]
+    /// elements according to the GFM autolink extension - bare `www.`, `http://` and `https://`
+    /// links and email addresses
+    fn parse_autolinks(inlines: Vec<Inline>) -> Vec<Inline> {
+        let mut result = Vec::with_capacity(inlines.len());
+        for inline in inlines {
+            match inline {
+                Inline::Str(s) => Self::split_autolinks(&s, &mut result),
+                Inline::Emph(i) => result.push(Inline::Emph(Self::parse_autolinks(i))),
+                Inline::Strong(i) => result.push(Inline::Strong(Self::parse_autolinks(i))),
+                Inline::Strikeout(i) => result.push(Inline::Strikeout(Self::parse_autolinks(i))),
+                i => result.push(i),
+            }
+        }
+        result
+    }
+
+    /// Finds autolinks in a text pushing text fragments as [`Inline::Str`] and the found links as
+    /// [`Inline::Link`] into the `result` argument
+    fn split_autolinks(text: &str, result: &mut Vec<Inline>) {
+        let mut text_start = 0;
+        let mut skip_until = 0;
+        let mut prev: Option<char> = None;
+        for (i, c) in text.char_indices() {
+            if i < skip_until {
+                prev = Some(c);
+                continue;
+            }
+            debug_assert!(text.is_char_boundary(i));
+            // Safety: index from CharIndices
+            let rest = unsafe { text.get_unchecked(i..) };
+            let url_boundary = prev.is_none_or(|p| {
+                p.is_whitespace() || matches!(p, '*' | '_' | '~' | '(')
+            });
+            let email_boundary = prev.is_none_or(|p| !Self::is_email_local_char(p));
+            let found = if url_boundary { Self::match_url(rest) } else { None }
+                .or_else(|| if email_boundary { Self::match_email(rest) } else { None });
+            if let Some((length, url)) = found {
+                if text_start < i {
+                    result.push(Inline::Str(text[text_start..i].to_owned()));
+                }
+                result.push(Inline::Link(
+                    attr_empty(),
+                    vec![Inline::Str(rest[..length].to_owned())],
+                    (url, String::new()),
+                ));
+                text_start = i + length;
+                skip_until = text_start;
+            }
+            prev = Some(c);
+        }
+        if text_start < text.len() {
+            result.push(Inline::Str(text[text_start..].to_owned()));
+        }
+    }
+
+    /// Checks if a text begins with an extended `www.`, `http://` or `https://` autolink. Returns
+    /// the length of the link in the text and the link destination
+    fn match_url(text: &str) -> Option<(usize, String)> {
+        let (domain_start, prefix) = if text.starts_with("www.") {
+            (0, "http://")
+        } else if text.starts_with("http://") {
+            ("http://".len(), "")
+        } else if text.starts_with("https://") {
+            ("https://".len(), "")
+        } else {
+            return None;
+        };
+        let domain_length = Self::valid_domain_length(&text[domain_start..])?;
+        let end = text[domain_start + domain_length..]
+            .find(|c: char| c == '<' || c.is_whitespace())
+            .map_or(text.len(), |e| domain_start + domain_length + e);
+        let length = Self::trim_autolink_end(&text[..end]).len();
+        (length > domain_start).then(|| (length, String::from(prefix) + &text[..length]))
+    }
+
+    /// Checks if a text begins with a valid domain returning its length. A valid domain consists
+    /// of segments of alphanumeric characters, `'_'` and `'-'` separated by periods, has at least
+    /// one period and the last two segments have no underscores
+    fn valid_domain_length(text: &str) -> Option<usize> {
+        let length = text
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.')))
+            .unwrap_or(text.len());
+        let domain = text[..length].trim_end_matches('.');
+        let mut segments = domain.rsplit('.');
+        let last = segments.next()?;
+        let second = segments.next()?;
+        if last.is_empty() || second.is_empty() || last.contains('_') || second.contains('_') {
+            return None;
+        }
+        Some(domain.len())
+    }
+
+    /// Removes trailing punctuation from an autolink, unbalanced closing parentheses and trailing
+    /// entity references
+    fn trim_autolink_end(mut link: &str) -> &str {
+        loop {
+            let trimmed = link.trim_end_matches(['?', '!', '.', ',', ':', '*', '_', '~']);
+            let trimmed = if trimmed.ends_with(')')
+                && trimmed.matches(')').count() > trimmed.matches('(').count()
+            {
+                &trimmed[..trimmed.len() - 1]
+            } else if let Some(without) = trimmed.strip_suffix(';') {
+                let name = without.trim_end_matches(|c: char| c.is_ascii_alphanumeric());
+                if name.ends_with('&') && name.len() < without.len() {
+                    &name[..name.len() - 1]
+                } else {
+                    trimmed
+                }
+            } else {
+                trimmed
+            };
+            if trimmed.len() == link.len() {
+                return link;
+            }
+            link = trimmed;
+        }
+    }
+
+    /// Returns whether a char can be a part of the local part of an email autolink
+    const fn is_email_local_char(c: char) -> bool {
+        c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '_')
+    }
+
+    /// Checks if a text begins with an extended email autolink. Returns the length of the link in
+    /// the text and the link destination
+    fn match_email(text: &str) -> Option<(usize, String)> {
+        let at = text.find(|c| !Self::is_email_local_char(c))?;
+        if at == 0 || !text[at..].starts_with('@') {
+            return None;
+        }
+        let domain = &text[at + 1..];
+        let mut length = domain
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            .unwrap_or(domain.len());
+        if domain[..length].ends_with('.') {
+            length -= 1;
+        }
+        let domain = &domain[..length];
+        if !domain.contains('.') || domain.ends_with(['-', '_']) || domain.starts_with('.') {
+            return None;
+        }
+        let length = at + 1 + length;
+        Some((length, String::from("mailto:") + &text[..length]))
+    }
+
+    /// Replaces inlines between pairs of `marker` found in [`Inline::Str`] elements (also nested in
+    /// emphasis) with an element created from them by `create`. An opening marker can't be
+    /// followed by whitespace and a closing marker can't be preceded by whitespace. If `spaces` is
+    /// false the content can't contain whitespace at all. Backslash escaped markers are kept as
+    /// separate elements by [`Self::handle_backslash`] so they are not matched
+    fn parse_marker_pairs(
+        inlines: Vec<Inline>, marker: &str, spaces: bool, create: &impl Fn(Vec<Inline>) -> Inline,
+    ) -> Vec<Inline> {
+        let is_marker = |i: Option<&Inline>| matches!(i, Some(Inline::Str(s)) if s == marker);
+        let is_space = |i: Option<&Inline>| {
+            matches!(i, None | Some(Inline::Space | Inline::SoftBreak | Inline::LineBreak))
+        };
+        let mut tokens = Vec::with_capacity(inlines.len());
+        for inline in inlines {
+            match inline {
+                Inline::Str(s) =>
+                    for (i, part) in s.split(marker).enumerate() {
+                        if i > 0 {
+                            tokens.push(Inline::Str(marker.to_owned()));
+                        }
+                        if !part.is_empty() {
+                            tokens.push(Inline::Str(part.to_owned()));
+                        }
+                    },
+                Inline::Emph(i) =>
+                    tokens.push(Inline::Emph(Self::parse_marker_pairs(i, marker, spaces, create))),
+                Inline::Strong(i) => tokens
+                    .push(Inline::Strong(Self::parse_marker_pairs(i, marker, spaces, create))),
+                Inline::Strikeout(i) => tokens
+                    .push(Inline::Strikeout(Self::parse_marker_pairs(i, marker, spaces, create))),
+                i => tokens.push(i),
+            }
+        }
+        let mut result: Vec<Inline> = Vec::with_capacity(tokens.len());
+        let mut opener = None;
+        for (i, token) in tokens.iter().enumerate() {
+            if is_marker(Some(token)) {
+                let prev = i.checked_sub(1).and_then(|p| tokens.get(p));
+                match opener {
+                    Some(o) if !is_space(prev) && o + 1 < result.len() => {
+                        let content = result.split_off(o + 1);
+                        result.pop();
+                        result.push(create(Self::merge_strs(content)));
+                        opener = None;
+                        continue;
+                    },
+                    _ if !is_space(tokens.get(i + 1)) => opener = Some(result.len()),
+                    _ => {},
+                }
+            } else if !spaces && is_space(Some(token)) {
+                opener = None;
+            }
+            result.push(token.clone());
+        }
+        Self::merge_strs(result)
+    }
+
+    /// Merges adjacent [`Inline::Str`] elements
+    fn merge_strs(inlines: Vec<Inline>) -> Vec<Inline> {
+        let mut result: Vec<Inline> = Vec::with_capacity(inlines.len());
+        for inline in inlines {
+            match (result.last_mut(), inline) {
+                (Some(Inline::Str(last)), Inline::Str(s)) => last.push_str(&s),
+                (_, inline) => result.push(inline),
+            }
+        }
+        result
+    }
+
+    /// Splits [`Inline::Str`] elements (also nested in emphasis) into text and [`Inline::Cite`]
+    /// elements for in-text `@key` citations according to Pandoc citation syntax. Bracketed
+    /// citations are parsed by [`Self::citation_brackets`]
+    fn parse_citations(inlines: Vec<Inline>) -> Vec<Inline> {
+        let mut result = Vec::with_capacity(inlines.len());
+        for inline in inlines {
+            match inline {
+                Inline::Str(s) => Self::split_citations(&s, &mut result),
+                Inline::Emph(i) => result.push(Inline::Emph(Self::parse_citations(i))),
+                Inline::Strong(i) => result.push(Inline::Strong(Self::parse_citations(i))),
+                Inline::Strikeout(i) => result.push(Inline::Strikeout(Self::parse_citations(i))),
+                i => result.push(i),
+            }
+        }
+        result
+    }
+
+    /// Finds in-text citations in a text pushing text fragments as [`Inline::Str`] and the found
+    /// citations as [`Inline::Cite`] into the `result` argument
+    fn split_citations(text: &str, result: &mut Vec<Inline>) {
+        let mut text_start = 0;
+        let mut i = 0;
+        let mut prev: Option<char> = None;
+        while let Some(c) = text[i..].chars().next() {
+            let found = match c {
+                '@' if prev.is_none_or(char::is_whitespace) =>
+                    Self::citation_key(&text[i + 1..]).map(|(key, _)| {
+                        let mode = CitationMode::AuthorInText;
+                        let citation = Self::citation(key, mode, Vec::new(), Vec::new());
+                        let content = vec![Inline::Str(format!("@{key}"))];
+                        (key.len() + 1, Inline::Cite(vec![citation], content))
+                    }),
+                _ => None,
+            };
+            if let Some((length, cite)) = found {
+                if text_start < i {
+                    result.push(Inline::Str(text[text_start..i].to_owned()));
+                }
+                result.push(cite);
+                i += length;
+                text_start = i;
+                prev = text[..i].chars().next_back();
+            } else {
+                prev = Some(c);
+                i += c.len_utf8();
+            }
+        }
+        if text_start < text.len() {
+            result.push(Inline::Str(text[text_start..].to_owned()));
+        }
+    }
+
+    /// Parses the content of brackets as a list of citations separated with `;`. Each citation
+    /// consists of an optional prefix, a key preceded by `@` (or `-@` to suppress the author) and
+    /// an optional suffix, both parsed as inlines. Returns `None` if any of them doesn't contain a
+    /// key
+    fn bracketed_citations(
+        content: &str, links: &Links, options: MdReaderOptions,
+    ) -> Option<Vec<Citation>> {
+        content
+            .split(';')
+            .map(|part| {
+                let part = part.trim();
+                let mut prev = None;
+                let mut before_prev = None;
+                let (prefix_end, key_start, mode) = part.char_indices().find_map(|(j, c)| {
+                    let found = match (before_prev, prev, c) {
+                        (_, None | Some(' ' | '\t' | '\n'), '@') =>
+                            Some((j, j + 1, CitationMode::NormalCitation)),
+                        (None | Some(' ' | '\t' | '\n'), Some('-'), '@') =>
+                            Some((j - 1, j + 1, CitationMode::SuppressAuthor)),
+                        _ => None,
+                    };
+                    before_prev = prev;
+                    prev = Some(c);
+                    found
+                })?;
+                let (key, suffix) = Self::citation_key(&part[key_start..])?;
+                let prefix = Self::parse_lines(part[..prefix_end].trim_end(), links, options);
+                let mut suffix_inlines = Self::parse_lines(suffix, links, options);
+                if suffix.starts_with(char::is_whitespace) && !suffix_inlines.is_empty() {
+                    suffix_inlines.insert(0, Inline::Space);
+                }
+                Some(Self::citation(key, mode, prefix, suffix_inlines))
+            })
+            .collect()
+    }
+
+    /// Creates a [`Citation`] with a prefix and suffix
+    fn citation(
+        id: &str, mode: CitationMode, prefix: Vec<Inline>, suffix: Vec<Inline>,
+    ) -> Citation {
+        Citation {
+            id: id.to_owned(),
+            prefix,
+            suffix,
+            mode,
+            note_num: 0,
+            hash: 0,
+        }
+    }
+
+    /// Checks if a text begins with a citation key. Keys start with an alphanumeric character or
+    /// an underscore and can contain single punctuation characters between them. Returns the key
+    /// and the rest of the text
+    fn citation_key(text: &str) -> Option<(&str, &str)> {
+        let mut end = 0;
+        for (j, c) in text.char_indices() {
+            if c.is_alphanumeric() || c == '_' {
+                end = j + c.len_utf8();
+            } else if j == 0
+                || !":.#$%&-+?<>~/".contains(c)
+                || !text[j + 1..].starts_with(|n: char| n.is_alphanumeric() || n == '_')
+            {
+                break;
+            }
+        }
+        (end > 0).then(|| text.split_at(end))
+    }
+
+    /// Splits a text into [`Inline::Str`] words separated by [`Inline::Space`]
+    fn text_inlines(text: &str) -> Vec<Inline> {
+        let mut result = Vec::new();
+        for (i, word) in text.split([' ', '\t', '\n']).enumerate() {
+            if i > 0 && result.last() != Some(&Inline::Space) {
+                result.push(Inline::Space);
+            }
+            if !word.is_empty() {
+                result.push(Inline::Str(word.to_owned()));
+            }
+        }
+        result
+    }
+
     /// Parses given code slice into a code span according to the rules in the GFM website
     fn parse_code_slice(slice: &str) -> InlineElement {
         let mut x = 0;
@@ -408,7 +905,7 @@ impl InlineParser {
     fn parse_inline_slice<'a>(
         slice: &'a str, result: &mut Vec<InlineElement<'a>>,
         last_opener_star: &mut [Option<usize>; 3], last_opener_floor: &mut [Option<usize>; 3],
-        mut is_beginning: bool, links: &Links,
+        mut is_beginning: bool, links: &Links, options: MdReaderOptions,
     ) -> Vec<DelimiterStruct<'a>> {
         let mut delimiter_stack: Vec<DelimiterStruct> = Vec::new();
         let mut is_space_stream: bool = false;
@@ -423,12 +920,21 @@ impl InlineParser {
         while let Some((start, c)) = char_iter.next() {
             match c {
                 '[' => Self::handle_open_bracket_temp(
-                    slice, result, &mut current, &current_begin, start, &mut char_iter, links,
+                    slice, result, &mut current, &mut current_begin, start, &mut char_iter,
+                    &mut is_prev_punctuation, &mut is_space_stream, links, options,
+                ),
+                '!' if slice[start + 1..].starts_with('[') => Self::handle_open_bracket_temp(
+                    slice, result, &mut current, &mut current_begin, start, &mut char_iter,
+                    &mut is_prev_punctuation, &mut is_space_stream, links, options,
                 ),
                 // ']' => Self::handle_close_bracket(
                 //     slice, result, &mut current, &current_begin, &mut delimiter_stack, start,
                 //     link_open, &mut parse_link, &mut char_iter,
                 // ),
+                '~' if !options.strikethrough => Self::handle_regular_char(
+                    c, &mut current, &mut current_begin, start, &mut is_prev_punctuation,
+                    &mut is_space_stream,
+                ),
                 '*' | '_' | '~' => Self::handle_special_char(
                     slice, result, &mut current, &mut current_begin, &mut char_iter, c, start,
                     &mut delimiter_stack, last_opener_star, last_opener_floor,
@@ -436,14 +942,23 @@ impl InlineParser {
                 ),
                 '\\' => Self::handle_backslash(
                     slice, result, &mut current, &mut current_begin, &mut char_iter, start,
-                    &mut is_prev_punctuation,
+                    &mut is_prev_punctuation, options,
                 ),
                 '&' => Self::handle_ampersand(&mut current, &mut char_iter, &mut html_current),
+                '<' if slice[start..].starts_with("<!--") => Self::handle_html(
+                    slice, result, &mut current, &mut current_begin, &mut char_iter, start,
+                    &mut is_prev_punctuation, &mut is_space_stream,
+                    if options.keep_comments { HtmlMode::Raw } else { HtmlMode::Strip },
+                ),
+                '<' if options.html != HtmlMode::Escape => Self::handle_html(
+                    slice, result, &mut current, &mut current_begin, &mut char_iter, start,
+                    &mut is_prev_punctuation, &mut is_space_stream, options.html,
+                ),
                 '\n' => Self::handle_newline(
                     slice, result, &mut current, &mut current_begin, start, &mut is_space_stream,
                 ),
-                c if Self::UNICODE_WHITESPACE.contains(&c) => Self::handle_whitespace(
-                    slice, result, &mut current, &current_begin, &mut char_iter,
+                c if c.is_whitespace() => Self::handle_whitespace(
+                    slice, result, &mut current, &mut current_begin, &mut char_iter,
                     &mut is_space_stream, c, start,
                 ),
                 c => Self::handle_regular_char(
@@ -464,41 +979,46 @@ impl InlineParser {
         delimiter_stack
     }
 
-    /// Method looks for a closed \[...\] bracket sequence and if the `is_second` parameter is true
-    /// checks whether it neighbors another potential closed bracket sequence
-    fn check_closed_bracket(
-        char_iter: &mut Peekable<CharIndices>, is_second: bool,
-    ) -> Option<usize> {
-        let mut prev_escape = false;
-        loop {
-            match char_iter.next() {
-                Some((end, ']')) => {
-                    if prev_escape {
-                        continue;
-                    }
-                    if is_second || char_iter.peek().is_some_and(|(_, y)| *y == '[') {
-                        return Some(end);
-                    }
-                },
-                Some((_, '\\')) => {
-                    prev_escape = true;
-                },
-                Some((..)) => {
-                    prev_escape = false;
-                },
-                None => {
-                    return None;
+    /// Finds the index of the bracket closing an opened one in the text following it. Brackets in
+    /// between have to be balanced and backslash escaped brackets are skipped
+    fn closing_bracket(text: &str) -> Option<usize> {
+        let mut depth = 0;
+        let mut iter = text.char_indices();
+        while let Some((i, c)) = iter.next() {
+            match c {
+                '\\' => {
+                    iter.next();
                 },
+                '[' => depth += 1,
+                ']' if depth == 0 => return Some(i),
+                ']' => depth -= 1,
+                _ => {},
             }
         }
+        None
     }
 
-    /// Method handling GFM links currently only working on reference links for example \[bar\]
+    /// Method handling GFM links and images, currently only working on inline links for example
+    /// \[foo\](/url) and shortcut reference links for example \[bar\]. Brackets not forming a link
+    /// are handled as regular chars
+    #[allow(clippy::too_many_arguments)]
     fn handle_open_bracket_temp<'a>(
         slice: &'a str, result: &mut Vec<InlineElement<'a>>, current: &mut String,
-        current_begin: &Option<usize>, start: usize, char_iter: &mut Peekable<CharIndices<'a>>,
-        links: &Links,
+        current_begin: &mut Option<usize>, start: usize, char_iter: &mut Peekable<CharIndices<'a>>,
+        is_prev_punctuation: &mut bool, is_space_stream: &mut bool, links: &Links,
+        options: MdReaderOptions,
     ) {
+        let image = slice[start..].starts_with('!');
+        let open = if image { start + 1 } else { start };
+        let parsed = Self::parse_link(slice, open, image, links, options)
+            .or_else(|| (!image).then(|| Self::citation_brackets(slice, start, links, options))?);
+        let Some((element, end)) = parsed else {
+            let c = if image { '!' } else { '[' };
+            Self::handle_regular_char(
+                c, current, current_begin, start, is_prev_punctuation, is_space_stream,
+            );
+            return;
+        };
         if !current.is_empty() {
             result.push(InlineElement {
                 element: Inline::Str(Self::parse_html_entities(&current.clone())),
@@ -506,27 +1026,96 @@ impl InlineParser {
             });
         }
         *current = String::new();
-        let mut temp_iter = char_iter.clone();
-        let Some(first_end) = Self::check_closed_bracket(&mut temp_iter, true) else {
-            return;
-        };
-        let link_ref = &slice[start + 1..first_end];
-        if let Some(Link { url, title }) = links.get(&Links::strip(link_ref)) {
-            result.push(InlineElement {
-                element: Inline::Link(
-                    attr_empty(),
-                    Vec::new(),
-                    (url.clone(), title.clone().unwrap_or_else(|| link_ref.to_owned())),
-                ),
-                slice: &slice[start..=first_end],
-            });
+        *current_begin = Some(end + 1);
+        *is_prev_punctuation = false;
+        *is_space_stream = false;
+        result.push(InlineElement { element, slice: &slice[start..=end] });
+        while char_iter.next_if(|&(i, _)| i <= end).is_some() {}
+    }
+
+    /// Parses a link, or an image if `image` is true, with the text starting with a bracket at the
+    /// `open` index. Returns the element and the index of its last char
+    fn parse_link(
+        slice: &str, open: usize, image: bool, links: &Links, options: MdReaderOptions,
+    ) -> Option<(Inline, usize)> {
+        let close = open + 1 + Self::closing_bracket(&slice[open + 1..])?;
+        let text = &slice[open + 1..close];
+        let create = if image { Inline::Image } else { Inline::Link };
+        if let Some((url, title, length)) =
+            slice[close + 1..].strip_prefix('(').and_then(Self::parse_link_tail)
+        {
+            let target = (Self::unescape(url), title.map(Self::unescape).unwrap_or_default());
+            // Links can't contain other links, so bare urls in the link text stay text
+            let text_options = MdReaderOptions { autolinks: image && options.autolinks, ..options };
+            let content = Self::parse_lines(text, links, text_options);
+            return Some((create(attr_empty(), content, target), close + 1 + length));
+        }
+        let Link { url, title } = if Self::is_other_syntax(text, options) {
+            links.get(&Links::strip(text))
         } else {
-            result.push(InlineElement {
-                element: Inline::Str(Self::parse_html_entities(&slice[start..=first_end])),
-                slice: &slice[start..=first_end],
-            });
+            links.resolve(text)
+        }?;
+        let element = if image {
+            let target = (url.clone(), title.clone().unwrap_or_default());
+            Inline::Image(attr_empty(), Self::parse_lines(text, links, options), target)
+        } else {
+            let target = (url.clone(), title.clone().unwrap_or_else(|| text.to_owned()));
+            Inline::Link(attr_empty(), Vec::new(), target)
+        };
+        Some((element, close))
+    }
+
+    /// Parses bracketed citations with the opening bracket at the `open` index, see
+    /// [`Self::bracketed_citations`]. Returns the element and the index of the closing bracket
+    fn citation_brackets(
+        slice: &str, open: usize, links: &Links, options: MdReaderOptions,
+    ) -> Option<(Inline, usize)> {
+        if !options.citations {
+            return None;
         }
-        *char_iter = temp_iter;
+        let close = open + 1 + Self::closing_bracket(&slice[open + 1..])?;
+        let citations = Self::bracketed_citations(&slice[open + 1..close], links, options)?;
+        let content = Self::text_inlines(&Self::parse_html_entities(&slice[open..=close]));
+        Some((Inline::Cite(citations, content), close))
+    }
+
+    /// Checks if bracketed text is used by other syntax, so it isn't meant as a link reference and
+    /// shouldn't be reported as unresolved
+    fn is_other_syntax(text: &str, options: MdReaderOptions) -> bool {
+        text.trim().is_empty()
+            || text.contains('[')
+            || options.citations && text.contains('@')
+            || options.alerts && text.starts_with('!')
+    }
+
+    /// Parses the part of an inline link after the opening `'('`, returns the destination, the
+    /// optional title and the length of the parsed part including the closing `')'`
+    fn parse_link_tail(rest: &str) -> Option<(&str, Option<&str>, usize)> {
+        let mut iter = Iter::new(rest);
+        iter.skip_whitespace_new_line();
+        let destination = match iter.peek() {
+            Some('<') => iter.get_link_destination()?,
+            _ => iter.get_inline_link_destination()?,
+        };
+        let before = iter.get_str().len();
+        iter.skip_whitespace_new_line();
+        let whitespace = iter.get_str().len() < before;
+        let title = match iter.peek() {
+            Some(c @ ('"' | '\'')) if whitespace => {
+                iter.next();
+                Some(iter.get_str_until_unescaped(c)?)
+            },
+            Some('(') if whitespace => {
+                iter.next();
+                Some(iter.get_str_until_unescaped_without(')', '(')?)
+            },
+            _ => None,
+        };
+        iter.skip_whitespace_new_line();
+        if !iter.next_if_eq(')') {
+            return None;
+        }
+        Some((destination, title, rest.len() - iter.get_str().len()))
     }
 
     // fn handle_open_bracket<'a>(
@@ -631,10 +1220,10 @@ impl InlineParser {
                 if c == x {
                     char_iter.next();
                     continue;
-                } else if Self::ASCII_PUNCTUATION.contains(&x) {
+                } else if Self::is_punctuation(x) {
                     followed_by_punctuation = true;
                     break;
-                } else if Self::UNICODE_WHITESPACE.contains(&x) {
+                } else if x.is_whitespace() {
                     followed_by_whitespace = true;
                     break;
                 }
@@ -678,7 +1267,10 @@ impl InlineParser {
             text_nodes.push(result.len());
             result.push(node);
         }
+        // Runs of more than two tildes can't open or close strikethrough and stay as text
         if end_slice - start > 2 && c == '~' {
+            *is_prev_punctuation = true;
+            *is_space_stream = false;
             return;
         }
         let typeof_delimiter = if is_left_run && is_right_run {
@@ -712,11 +1304,14 @@ impl InlineParser {
         *is_space_stream = false;
     }
 
-    /// Method handling character escaping and linebreaks according to GFM rules
+    /// Method handling character escaping and linebreaks according to GFM rules. Escaped `^` and
+    /// `=` chars are pushed as separate [`Inline::RawInline`] elements while superscript or
+    /// highlight is enabled, so they aren't matched by [`Self::parse_marker_pairs`]
+    #[allow(clippy::too_many_arguments)]
     fn handle_backslash<'a>(
         slice: &'a str, result: &mut Vec<InlineElement<'a>>, current: &mut String,
         current_begin: &mut Option<usize>, char_iter: &mut Peekable<CharIndices<'a>>, start: usize,
-        is_prev_punctuation: &mut bool,
+        is_prev_punctuation: &mut bool, options: MdReaderOptions,
     ) {
         if let Some((_, peek_char)) = char_iter.next() {
             if !Self::ASCII_PUNCTUATION.contains(&peek_char) {
@@ -739,6 +1334,24 @@ impl InlineParser {
                 });
                 return;
             }
+            if peek_char == '^' && options.superscript || peek_char == '=' && options.highlight {
+                if !current.is_empty() {
+                    result.push(InlineElement {
+                        element: Inline::Str(Self::parse_html_entities(current)),
+                        slice: &slice[current_begin.unwrap()..start],
+                    });
+                    *current = String::new();
+                }
+                result.push(InlineElement {
+                    element: Inline::RawInline(
+                        Format(Self::ESCAPED_MARKER.to_owned()),
+                        peek_char.to_string(),
+                    ),
+                    slice: &slice[start..start + 2],
+                });
+                *current_begin = Some(start + 2);
+                return;
+            }
             current.push(peek_char);
         }
     }
@@ -782,6 +1395,110 @@ impl InlineParser {
         }
     }
 
+    /// Method handling raw HTML according to the [`HtmlMode`] option, a `<` not starting HTML is
+    /// handled as a regular character
+    #[allow(clippy::too_many_arguments)]
+    fn handle_html<'a>(
+        slice: &'a str, result: &mut Vec<InlineElement<'a>>, current: &mut String,
+        current_begin: &mut Option<usize>, char_iter: &mut Peekable<CharIndices<'a>>, start: usize,
+        is_prev_punctuation: &mut bool, is_space_stream: &mut bool, mode: HtmlMode,
+    ) {
+        let Some(length) = Self::html_length(&slice[start..]) else {
+            Self::handle_regular_char(
+                '<', current, current_begin, start, is_prev_punctuation, is_space_stream,
+            );
+            return;
+        };
+        let end = start + length;
+        if !current.is_empty() {
+            result.push(InlineElement {
+                element: Inline::Str(Self::parse_html_entities(current)),
+                slice: &slice[current_begin.unwrap_or(start)..start],
+            });
+            *current = String::new();
+        }
+        if mode == HtmlMode::Raw {
+            result.push(InlineElement {
+                element: Inline::RawInline(Format(String::from("html")), slice[start..end].into()),
+                slice: &slice[start..end],
+            });
+        }
+        while char_iter.next_if(|&(i, _)| i < end).is_some() {}
+        *current_begin = Some(end);
+        *is_prev_punctuation = true;
+        *is_space_stream = false;
+    }
+
+    /// Returns the length of the HTML open tag, closing tag, comment, processing instruction,
+    /// declaration or CDATA section at the start of the text
+    pub fn html_length(text: &str) -> Option<usize> {
+        let rest = text.strip_prefix('<')?;
+        let until = |start: &str, end: &str| {
+            let rest = rest.strip_prefix(start)?;
+            rest.find(end).map(|i| 1 + start.len() + i + end.len())
+        };
+        if rest.starts_with("!--") {
+            return ["!-->", "!--->"]
+                .into_iter()
+                .find(|s| rest.starts_with(s))
+                .map_or_else(|| until("!--", "-->"), |s| Some(s.len() + 1));
+        }
+        if rest.starts_with("![CDATA[") {
+            return until("![CDATA[", "]]>");
+        }
+        if rest.starts_with('?') {
+            return until("?", "?>");
+        }
+        if rest.strip_prefix('!').is_some_and(|r| r.starts_with(|c: char| c.is_ascii_alphabetic()))
+        {
+            return until("!", ">");
+        }
+        let (closing, rest) = rest.strip_prefix('/').map_or((false, rest), |r| (true, r));
+        let mut rest = Self::html_name(rest, false)?;
+        if closing {
+            let rest = rest.trim_start_matches(Self::is_html_whitespace);
+            return rest.strip_prefix('>').map(|r| text.len() - r.len());
+        }
+        loop {
+            let trimmed = rest.trim_start_matches(Self::is_html_whitespace);
+            if let Some(r) = trimmed.strip_prefix("/>").or_else(|| trimmed.strip_prefix('>')) {
+                return Some(text.len() - r.len());
+            }
+            if trimmed.len() == rest.len() {
+                return None;
+            }
+            rest = Self::html_name(trimmed, true)?;
+            let value = rest.trim_start_matches(Self::is_html_whitespace);
+            if let Some(value) = value.strip_prefix('=') {
+                let value = value.trim_start_matches(Self::is_html_whitespace);
+                rest = Self::html_attribute_value(value)?;
+            }
+        }
+    }
+
+    /// Returns the text after a tag name or attribute name at the start of the text
+    fn html_name(text: &str, attribute: bool) -> Option<&str> {
+        let first = |c: char| c.is_ascii_alphabetic() || attribute && matches!(c, '_' | ':');
+        let next = |c: char| {
+            c.is_ascii_alphanumeric() || c == '-' || attribute && matches!(c, '_' | '.' | ':')
+        };
+        text.strip_prefix(first).map(|r| r.trim_start_matches(next))
+    }
+
+    /// Returns the text after an attribute value at the start of the text
+    fn html_attribute_value(text: &str) -> Option<&str> {
+        if let q @ ('"' | '\'') = text.chars().next()? {
+            return text[1..].find(q).map(|i| &text[i + 2..]);
+        }
+        let rest = text.trim_start_matches(|c: char| {
+            !Self::is_html_whitespace(c) && !matches!(c, '"' | '\'' | '=' | '<' | '>' | '`')
+        });
+        (rest.len() < text.len()).then_some(rest)
+    }
+
+    /// Checks if a char is whitespace allowed inside HTML tags
+    const fn is_html_whitespace(c: char) -> bool { matches!(c, ' ' | '\t' | '\n') }
+
     /// Handling soft line break behavior according to GFM rules
     fn handle_newline<'a>(
         slice: &'a str, result: &mut Vec<InlineElement<'a>>, current: &mut String,
@@ -807,7 +1524,7 @@ impl InlineParser {
     /// Handling whitespace behavior according to GFM rules
     fn handle_whitespace<'a>(
         slice: &'a str, result: &mut Vec<InlineElement<'a>>, current: &mut String,
-        current_begin: &Option<usize>, char_iter: &mut Peekable<CharIndices<'a>>,
+        current_begin: &mut Option<usize>, char_iter: &mut Peekable<CharIndices<'a>>,
         is_space_stream: &mut bool, c: char, start: usize,
     ) {
         if c == ' ' {
@@ -817,11 +1534,21 @@ impl InlineParser {
                     char_iter.next();
                     two_spaces = true;
                 } else if y == '\n' && two_spaces {
+                    if !current.is_empty() {
+                        result.push(InlineElement {
+                            element: Inline::Str(Self::parse_html_entities(&current.clone())),
+                            slice: &slice[current_begin.unwrap()..start],
+                        });
+                        *current = String::new();
+                    }
                     result.push(InlineElement {
                         element: Inline::LineBreak,
-                        slice: &slice[start..end],
+                        slice: &slice[start..=end],
                     });
-                    break;
+                    char_iter.next();
+                    *current_begin = Some(end + 1);
+                    *is_space_stream = true;
+                    return;
                 } else {
                     break;
                 }
@@ -848,7 +1575,7 @@ impl InlineParser {
         c: char, current: &mut String, current_begin: &mut Option<usize>, start: usize,
         is_prev_punctuation: &mut bool, is_space_stream: &mut bool,
     ) {
-        *is_prev_punctuation = Self::ASCII_PUNCTUATION.contains(&c);
+        *is_prev_punctuation = Self::is_punctuation(c);
         *is_space_stream = false;
         if current_begin.is_none() {
             *current_begin = Some(start);
@@ -856,12 +1583,32 @@ impl InlineParser {
         current.push(c);
     }
 
+    /// Checks if a char is a punctuation char for the flanking rules of delimiter runs. Non-ASCII
+    /// chars which aren't alphanumeric, whitespace or control chars are treated as Unicode
+    /// punctuation and symbols
+    fn is_punctuation(c: char) -> bool {
+        Self::ASCII_PUNCTUATION.contains(&c)
+            || !c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace() && !c.is_control()
+    }
+
+    /// Returns the byte offset of `slice` in `base`, or `None` if `slice` doesn't point into `base`
+    /// (e.g. it was replaced with a static empty string)
+    fn slice_offset(base: &str, slice: &str) -> Option<usize> {
+        let offset = (slice.as_ptr() as usize).checked_sub(base.as_ptr() as usize)?;
+        (offset + slice.len() <= base.len()).then_some(offset)
+    }
+
+    /// Checks if `base[lower..upper]` can be taken without panicking
+    const fn valid_bounds(base: &str, lower: usize, upper: usize) -> bool {
+        lower <= upper && base.is_char_boundary(lower) && base.is_char_boundary(upper)
+    }
+
     /// Method used for parsing possible emphasis strong strikethrough according to GFM rules
     #[allow(dead_code)]
     #[allow(clippy::too_many_lines)]
     fn parse_emph<'a>(
         base_string: &'a str, delimiter_stack: &mut [DelimiterStruct<'a>], stack_bottom: usize,
-        result_vec: &mut [InlineElement<'a>],
+        result_vec: &mut [InlineElement<'a>], options: MdReaderOptions,
     ) -> Vec<InlineElement<'a>> {
         let mut emph_vector: Vec<InlineElement> = Vec::new();
         for index in 0..delimiter_stack.len() {
@@ -874,6 +1621,10 @@ impl InlineParser {
                         continue;
                     }
                     for j in (0..index).rev() {
+                        // Delimiters cleared by an earlier match can't be matched anymore
+                        if delimiter_stack[j].typeof_delimiter == Potential::None {
+                            continue;
+                        }
                         if !matches!(delimiter_stack[j].typeof_delimiter, Potential::Closer)
                             && ((matches!(delimiter_stack[j].typeof_delimiter, Potential::Both)
                                 || matches!(delim.typeof_delimiter, Potential::Both))
@@ -884,19 +1635,26 @@ impl InlineParser {
                                 && matches!(delim.typeof_delimiter, Potential::Closer))
                                 && delimiter_stack[j].delimiter_char == delim.delimiter_char
                         {
-                            let lower_bound = delimiter_stack[j].delim_slice.as_ptr() as usize
-                                + delimiter_stack[j].delim_slice.len()
-                                - base_string.as_ptr() as usize;
+                            let Some(lower_bound) =
+                                Self::slice_offset(base_string, delimiter_stack[j].delim_slice)
+                                    .map(|o| o + delimiter_stack[j].delim_slice.len())
+                            else {
+                                continue;
+                            };
                             if lower_bound < stack_bottom {
                                 continue;
                             }
                             while delim.delim_slice.len() >= 2
                                 && delimiter_stack[j].delim_slice.len() >= 2
                             {
-                                let upper_bound = delim.delim_slice.as_ptr() as usize
-                                    - base_string.as_ptr() as usize
-                                    + 2;
-                                if lower_bound < stack_bottom || upper_bound < stack_bottom {
+                                let offset = Self::slice_offset(base_string, delim.delim_slice);
+                                let Some(upper_bound) = offset.map(|o| o + 2) else {
+                                    break;
+                                };
+                                if lower_bound < stack_bottom
+                                    || upper_bound < stack_bottom
+                                    || !Self::valid_bounds(base_string, lower_bound, upper_bound)
+                                {
                                     break;
                                 }
                                 result_vec[delimiter_stack[j].temp_vec.pop().unwrap()] =
@@ -967,6 +1725,15 @@ impl InlineParser {
                                         element: Inline::Strikeout(nested_inlines),
                                         slice: &base_string[lower_bound..upper_bound],
                                     });
+                                } else if delim.delimiter_char == '_' && options.underline {
+                                    result_vec[lower_res_index] = InlineElement {
+                                        element: Inline::Underline(nested_inlines.clone()),
+                                        slice: &base_string[lower_bound..upper_bound],
+                                    };
+                                    emph_vector.push(InlineElement {
+                                        element: Inline::Underline(nested_inlines),
+                                        slice: &base_string[lower_bound..upper_bound],
+                                    });
                                 } else {
                                     result_vec[lower_res_index] = InlineElement {
                                         element: Inline::Strong(nested_inlines.clone()),
@@ -990,10 +1757,22 @@ impl InlineParser {
                                     delimiter.count = 0;
                                 }
                             }
-                            if !delim.delim_slice.is_empty()
-                                && !delimiter_stack[j].delim_slice.is_empty()
-                                && delim.delimiter_char == delimiter_stack[j].delimiter_char
+                            if delim.delim_slice.is_empty()
+                                || delimiter_stack[j].delim_slice.is_empty()
+                                || delim.delimiter_char != delimiter_stack[j].delimiter_char
                             {
+                                continue;
+                            }
+                            let (Some(lower_offset), Some(upper_offset)) = (
+                                Self::slice_offset(base_string, delimiter_stack[j].delim_slice),
+                                Self::slice_offset(base_string, delim.delim_slice),
+                            ) else {
+                                continue;
+                            };
+                            let lower_bound =
+                                lower_offset + delimiter_stack[j].delim_slice.len() - 1;
+                            let upper_bound = upper_offset + 1;
+                            if Self::valid_bounds(base_string, lower_bound, upper_bound) {
                                 let lower_res_index = delimiter_stack[j].temp_vec.pop().unwrap();
                                 let upper_res_index = delim.temp_vec.remove(0);
                                 result_vec[upper_res_index] =
@@ -1037,32 +1816,15 @@ impl InlineParser {
                                     }
                                 }
 
-                                let lower_bound = delimiter_stack[j].delim_slice.as_ptr() as usize
-                                    - base_string.as_ptr() as usize
-                                    + delimiter_stack[j].delim_slice.len()
-                                    - 1;
-                                let upper_bound = delim.delim_slice.as_ptr() as usize
-                                    - base_string.as_ptr() as usize
-                                    + 1;
-                                if delim.delimiter_char == '~' {
-                                    result_vec[lower_res_index] = InlineElement {
-                                        element: Inline::Strikeout(nested_inlines.clone()),
-                                        slice: &base_string[lower_bound..upper_bound],
-                                    };
-                                    emph_vector.push(InlineElement {
-                                        element: Inline::Strikeout(nested_inlines),
-                                        slice: &base_string[lower_bound..upper_bound],
-                                    });
-                                } else {
-                                    result_vec[lower_res_index] = InlineElement {
-                                        element: Inline::Emph(nested_inlines.clone()),
-                                        slice: &base_string[lower_bound..upper_bound],
-                                    };
-                                    emph_vector.push(InlineElement {
-                                        element: Inline::Emph(nested_inlines),
-                                        slice: &base_string[lower_bound..upper_bound],
-                                    });
-                                }
+                                let element = match delim.delimiter_char {
+                                    '~' if options.subscript => Inline::Subscript(nested_inlines),
+                                    '~' => Inline::Strikeout(nested_inlines),
+                                    _ => Inline::Emph(nested_inlines),
+                                };
+                                let slice = &base_string[lower_bound..upper_bound];
+                                result_vec[lower_res_index] =
+                                    InlineElement { element: element.clone(), slice };
+                                emph_vector.push(InlineElement { slice, element });
                                 let bottom_index =
                                     lower_bound + 1 - delimiter_stack[j].delim_slice.len();
                                 delimiter_stack[j]
@@ -1091,12 +1853,18 @@ impl InlineParser {
 mod test {
     use super::*;
 
+    fn parse(text: &str) -> Vec<Inline> { parse_with(text, MdReaderOptions::default()) }
+
+    fn parse_with(text: &str, options: MdReaderOptions) -> Vec<Inline> {
+        InlineParser::parse_lines(text, &Links::new(), options)
+    }
+
     #[test]
     fn test_test() {
         // let result = MdReader::read("> ```\n> aaa\n\nbbb").into_ok();
         let test = String::from("hello        rust \\'");
 
-        let result = InlineParser::parse_lines(&test, &Links::new());
+        let result = parse(&test);
         assert_eq!(Inline::Str("hello".to_string()), result[0]);
         assert_eq!(Inline::Space, result[1]);
         assert_eq!(Inline::Str("rust".to_string()), result[2]);
@@ -1107,7 +1875,7 @@ mod test {
     #[test]
     fn html_entity_dec_test() {
         let test = String::from("&#42;  asdfsasdasdasffs");
-        let result = InlineParser::parse_lines(&test, &Links::new());
+        let result = parse(&test);
         let Inline::Str(s) = &result[0] else { return };
         assert_eq!(s.to_string(), String::from("*"));
         assert_eq!(Inline::Space, result[1]);
@@ -1118,7 +1886,7 @@ mod test {
     #[test]
     fn html_entity_hex_test() {
         let test = String::from("&#x2A;  asdfsasdasdasffsasdf");
-        let result = InlineParser::parse_lines(&test, &Links::new());
+        let result = parse(&test);
         let Inline::Str(s) = &result[0] else { return };
         assert_eq!(s.to_string(), String::from("*"));
         assert_eq!(Inline::Space, result[1]);
@@ -1129,10 +1897,459 @@ mod test {
     #[test]
     fn code_span_test() {
         let test = String::from("``` abc ```");
-        let result = InlineParser::parse_lines(&test, &Links::new());
+        let result = parse(&test);
         let Inline::Code(_, s) = &result[0] else {
             panic!("Test failed :(");
         };
         assert_eq!(s.to_string(), String::from("abc"));
     }
+
+    fn autolink(text: &str, url: &str) -> Inline {
+        let content = vec![Inline::Str(text.to_owned())];
+        Inline::Link(attr_empty(), content, (url.to_owned(), String::new()))
+    }
+
+    #[test]
+    fn autolinks() {
+        let result = parse("see https://x.com.");
+        assert_eq!(result, vec![
+            Inline::Str("see".to_owned()),
+            Inline::Space,
+            autolink("https://x.com", "https://x.com"),
+            Inline::Str(".".to_owned()),
+        ]);
+        let result = parse("www.x.com");
+        assert_eq!(result, vec![autolink("www.x.com", "http://www.x.com")]);
+        let result = parse("(www.x.com/a(b))))");
+        assert_eq!(result, vec![
+            Inline::Str("(".to_owned()),
+            autolink("www.x.com/a(b)", "http://www.x.com/a(b)"),
+            Inline::Str(")))".to_owned()),
+        ]);
+        let result = parse("mail foo@bar.baz.");
+        assert_eq!(result[2], autolink("foo@bar.baz", "mailto:foo@bar.baz"));
+        let result = parse("www.x_y.com and awww.x.com");
+        assert!(result.iter().all(|i| !matches!(i, Inline::Link(..))));
+    }
+
+    #[test]
+    fn inline_links() {
+        let link = |content: &str, url: &str, title: &str| {
+            Inline::Link(
+                attr_empty(),
+                vec![Inline::Str(content.to_owned())],
+                (url.to_owned(), title.to_owned()),
+            )
+        };
+        assert_eq!(parse("[x](<url with spaces>)"), vec![link("x", "url with spaces", "")]);
+        assert_eq!(parse("[x](/url \"title\")"), vec![link("x", "/url", "title")]);
+        assert_eq!(parse("[x](/a(b)c)"), vec![link("x", "/a(b)c", "")]);
+        assert_eq!(parse(r#"[x](u\*v "t\*")"#), vec![link("x", "u*v", "t*")]);
+        assert_eq!(parse(r"[x](<a\>b> 't\'&amp;')"), vec![link("x", "a>b", "t'&")]);
+        assert_eq!(parse(r"[x](a\b\&amp;)"), vec![link("x", r"a\b&amp;", "")]);
+        assert_eq!(parse("a [x](/url) b"), vec![
+            Inline::Str("a".to_owned()),
+            Inline::Space,
+            link("x", "/url", ""),
+            Inline::Space,
+            Inline::Str("b".to_owned()),
+        ]);
+        assert_eq!(parse("[x](<a>b)"), vec![Inline::Str("[x](<a>b)".to_owned())]);
+        assert_eq!(parse("[x"), vec![Inline::Str("[x".to_owned())]);
+        assert_eq!(parse("[http://c.d](http://c.d)"), vec![link("http://c.d", "http://c.d", "")]);
+        let content = ["see", "www.x.com"].map(|s| Inline::Str(s.to_owned()));
+        assert_eq!(parse("[see www.x.com](http://y.z)"), vec![Inline::Link(
+            attr_empty(),
+            vec![content[0].clone(), Inline::Space, content[1].clone()],
+            (String::from("http://y.z"), String::new()),
+        )]);
+    }
+
+    #[test]
+    fn images() {
+        let str = |s: &str| Inline::Str(s.to_owned());
+        let target = |url: &str, title: &str| (url.to_owned(), title.to_owned());
+        let image = Inline::Image(attr_empty(), vec![str("a")], target("/img.png", "title"));
+        assert_eq!(parse("![a](/img.png \"title\")"), vec![image.clone()]);
+        let link = Inline::Link(attr_empty(), vec![image], target("/url", ""));
+        assert_eq!(parse("[![a](/img.png \"title\")](/url)"), vec![link]);
+        let content = vec![str("a"), Inline::Space, str("[b]"), Inline::Space, str("c")];
+        let link = Inline::Link(attr_empty(), content, target("/url", ""));
+        assert_eq!(parse("[a [b] c](/url)"), vec![link]);
+        assert_eq!(parse("![a] ! [b]"), vec![
+            str("![a]"),
+            Inline::Space,
+            str("!"),
+            Inline::Space,
+            str("[b]"),
+        ]);
+        assert_eq!(parse("[*a*]"), vec![str("["), Inline::Emph(vec![str("a")]), str("]")]);
+    }
+
+    #[test]
+    fn overlapping_emphasis() {
+        // Matching `_` clears the `*` between them, which used to break the offset calculations
+        // for the following delimiters
+        let result = parse("_*_*.");
+        assert_eq!(result, vec![
+            Inline::Emph(vec![Inline::Str("*".to_owned())]),
+            Inline::Str("*.".to_owned()),
+        ]);
+        for test in ["_*_~.", "*_*~.", "x _*_**.", "__*__**."] {
+            assert!(!parse(test).is_empty());
+        }
+        let result = parse("_a *b_ c*");
+        assert_eq!(result, vec![
+            Inline::Emph(vec![
+                Inline::Str("a".to_owned()),
+                Inline::Space,
+                Inline::Str("*b".to_owned()),
+            ]),
+            Inline::Space,
+            Inline::Str("c*".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn rule_of_three() {
+        let str = |s: &str| Inline::Str(s.to_owned());
+        let (emph, strong) = (Inline::Emph, Inline::Strong);
+        assert_eq!(parse("*foo**bar**baz*"), vec![emph(vec![
+            str("foo"),
+            strong(vec![str("bar")]),
+            str("baz"),
+        ])]);
+        assert_eq!(parse("**foo*bar*baz**"), vec![strong(vec![
+            str("foo"),
+            emph(vec![str("bar")]),
+            str("baz"),
+        ])]);
+        assert_eq!(parse("*foo**bar*"), vec![emph(vec![str("foo**bar")])]);
+        assert_eq!(parse("**foo*bar**"), vec![strong(vec![str("foo*bar")])]);
+        assert_eq!(parse("***foo** bar*"), vec![emph(vec![
+            strong(vec![str("foo")]),
+            Inline::Space,
+            str("bar"),
+        ])]);
+        assert_eq!(parse("*foo **bar***"), vec![emph(vec![
+            str("foo"),
+            Inline::Space,
+            strong(vec![str("bar")]),
+        ])]);
+        assert_eq!(parse("*foo**bar***"), vec![emph(vec![str("foo"), strong(vec![str("bar")])])]);
+        assert_eq!(parse("foo***bar***baz"), vec![
+            str("foo"),
+            emph(vec![strong(vec![str("bar")])]),
+            str("baz"),
+        ]);
+        assert_eq!(parse("foo******bar*********baz"), vec![
+            str("foo"),
+            strong(vec![strong(vec![strong(vec![str("bar")])])]),
+            str("***baz"),
+        ]);
+        assert_eq!(parse("*foo*bar**"), vec![emph(vec![str("foo")]), str("bar**")]);
+        assert_eq!(parse("__foo_bar_baz__"), vec![strong(vec![str("foo_bar_baz")])]);
+        assert_eq!(parse("_foo__bar__baz_"), vec![emph(vec![str("foo__bar__baz")])]);
+    }
+
+    #[test]
+    fn unicode_punctuation() {
+        let str = |s: &str| Inline::Str(s.to_owned());
+        assert_eq!(parse("a*«x»*b"), vec![str("a*«x»*b")]);
+        assert_eq!(parse("a*„x“*"), vec![str("a*„x“*")]);
+        assert_eq!(parse("«*x*»"), vec![str("«"), Inline::Emph(vec![str("x")]), str("»")]);
+        assert_eq!(parse("*«x»*"), vec![Inline::Emph(vec![str("«x»")])]);
+        assert_eq!(parse("€_x_€"), vec![str("€"), Inline::Emph(vec![str("x")]), str("€")]);
+        assert_eq!(parse("ä_x_ä"), vec![str("ä_x_ä")]);
+        assert_eq!(parse("\\+"), vec![str("+")]);
+    }
+
+    #[test]
+    fn unicode_whitespace() {
+        let str = |s: &str| Inline::Str(s.to_owned());
+        assert_eq!(parse("a\u{2028}*x*\u{2028}b"), vec![
+            str("a"),
+            Inline::Space,
+            Inline::Emph(vec![str("x")]),
+            Inline::Space,
+            str("b"),
+        ]);
+        assert_eq!(parse("*\u{2028}x*"), vec![str("*"), Inline::Space, str("x*")]);
+        assert_eq!(parse("*x\u{2028}*"), vec![str("*x"), Inline::Space, str("*")]);
+        assert_eq!(parse("_\u{3000}x_"), vec![str("_"), Inline::Space, str("x_")]);
+    }
+
+    #[test]
+    fn citations() {
+        let options = MdReaderOptions { citations: true, ..Default::default() };
+        let citation = |id: &str, mode, prefix: Vec<Inline>, suffix: Vec<Inline>| Citation {
+            id: id.to_owned(),
+            prefix,
+            suffix,
+            mode,
+            note_num: 0,
+            hash: 0,
+        };
+        let str = |s: &str| Inline::Str(s.to_owned());
+        let result = parse_with("[@smith2020]", options);
+        assert_eq!(result, vec![Inline::Cite(
+            vec![citation("smith2020", CitationMode::NormalCitation, Vec::new(), Vec::new())],
+            vec![str("[@smith2020]")],
+        )]);
+        let result = parse_with("[see @smith2020, p. 5]", options);
+        let suffix = vec![str(","), Inline::Space, str("p."), Inline::Space, str("5")];
+        assert_eq!(result, vec![Inline::Cite(
+            vec![citation("smith2020", CitationMode::NormalCitation, vec![str("see")], suffix)],
+            vec![
+                str("[see"),
+                Inline::Space,
+                str("@smith2020,"),
+                Inline::Space,
+                str("p."),
+                Inline::Space,
+                str("5]"),
+            ],
+        )]);
+        let result = parse_with("[@a; -@b.]", options);
+        let Inline::Cite(citations, _) = &result[0] else { panic!() };
+        assert_eq!(citations, &vec![
+            citation("a", CitationMode::NormalCitation, Vec::new(), Vec::new()),
+            citation("b", CitationMode::SuppressAuthor, Vec::new(), vec![str(".")]),
+        ]);
+        let result = parse_with("[*see* @a, p. **5**]", options);
+        let Inline::Cite(citations, _) = &result[0] else { panic!() };
+        let strong = Inline::Strong(vec![str("5")]);
+        let suffix = vec![str(","), Inline::Space, str("p."), Inline::Space, strong];
+        assert_eq!(citations, &vec![citation(
+            "a",
+            CitationMode::NormalCitation,
+            vec![Inline::Emph(vec![str("see")])],
+            suffix,
+        )]);
+        let result = parse_with("[mail *me* at a@b.c]", options);
+        let emph = Inline::Emph(vec![str("me")]);
+        assert_eq!(result[..4], [str("[mail"), Inline::Space, emph, Inline::Space]);
+        let result = parse_with(r"\[@a]", options);
+        assert_eq!(result, vec![str("[@a]")]);
+        let result = parse_with("as @doe:99 says", options);
+        assert_eq!(result[2], Inline::Cite(
+            vec![citation("doe:99", CitationMode::AuthorInText, Vec::new(), Vec::new())],
+            vec![str("@doe:99")],
+        ));
+        let result = parse_with("[@a] a@b.c", options);
+        assert!(matches!(result[..], [Inline::Cite(..), Inline::Space, Inline::Link(..)]));
+        let result = parse("[@smith2020]");
+        assert_eq!(result, vec![str("[@smith2020]")]);
+    }
+
+    #[test]
+    fn superscript() {
+        let options = MdReaderOptions { superscript: true, ..Default::default() };
+        let result = parse_with("2^10^ is a^b c^", options);
+        assert_eq!(result, vec![
+            Inline::Str("2".to_owned()),
+            Inline::Superscript(vec![Inline::Str("10".to_owned())]),
+            Inline::Space,
+            Inline::Str("is".to_owned()),
+            Inline::Space,
+            Inline::Str("a^b".to_owned()),
+            Inline::Space,
+            Inline::Str("c^".to_owned()),
+        ]);
+        assert_eq!(parse("2^10^"), vec![Inline::Str("2^10^".to_owned())]);
+        let result = parse_with(r"2\^10\^ *2^\^^*", options);
+        assert_eq!(result, vec![
+            Inline::Str("2^10^".to_owned()),
+            Inline::Space,
+            Inline::Emph(vec![
+                Inline::Str("2".to_owned()),
+                Inline::Superscript(vec![Inline::Str("^".to_owned())]),
+            ]),
+        ]);
+        let options = MdReaderOptions { highlight: true, ..options };
+        let text = "a\u{FDD0}b\u{FDD1}";
+        assert_eq!(parse_with(text, options), vec![Inline::Str(text.to_owned())]);
+    }
+
+    #[test]
+    fn subscript() {
+        let options = MdReaderOptions { subscript: true, ..Default::default() };
+        let result = parse_with("H~2~O ~~gone~~", options);
+        assert_eq!(result, vec![
+            Inline::Str("H".to_owned()),
+            Inline::Subscript(vec![Inline::Str("2".to_owned())]),
+            Inline::Str("O".to_owned()),
+            Inline::Space,
+            Inline::Strikeout(vec![Inline::Str("gone".to_owned())]),
+        ]);
+        let result = parse("H~2~O");
+        assert_eq!(result[1], Inline::Strikeout(vec![Inline::Str("2".to_owned())]));
+    }
+
+    #[test]
+    fn highlight() {
+        let options = MdReaderOptions { highlight: true, ..Default::default() };
+        let result = parse_with("a ==very *important* text== == b ==", options);
+        let class = vec![String::from("highlight")];
+        assert_eq!(result, vec![
+            Inline::Str("a".to_owned()),
+            Inline::Space,
+            Inline::Span((String::new(), class, Vec::new()), vec![
+                Inline::Str("very".to_owned()),
+                Inline::Space,
+                Inline::Emph(vec![Inline::Str("important".to_owned())]),
+                Inline::Space,
+                Inline::Str("text".to_owned()),
+            ]),
+            Inline::Space,
+            Inline::Str("==".to_owned()),
+            Inline::Space,
+            Inline::Str("b".to_owned()),
+            Inline::Space,
+            Inline::Str("==".to_owned()),
+        ]);
+        assert_eq!(parse("==a=="), vec![Inline::Str("==a==".to_owned())]);
+        let result = parse_with(r"\=\=a\=\= ==a\=b==", options);
+        assert_eq!(result, vec![
+            Inline::Str("==a==".to_owned()),
+            Inline::Space,
+            Inline::Span((String::new(), vec![String::from("highlight")], Vec::new()), vec![
+                Inline::Str("a=b".to_owned()),
+            ]),
+        ]);
+    }
+
+    #[test]
+    fn underline() {
+        let options = MdReaderOptions { underline: true, ..Default::default() };
+        let x = || vec![Inline::Str("x".to_owned())];
+        assert_eq!(parse_with("__x__", options), vec![Inline::Underline(x())]);
+        assert_eq!(parse_with("**x**", options), vec![Inline::Strong(x())]);
+        assert_eq!(parse_with("_x_", options), vec![Inline::Emph(x())]);
+        let nested = vec![Inline::Emph(vec![Inline::Underline(x())])];
+        assert_eq!(parse_with("___x___", options), nested);
+        assert_eq!(parse("__x__"), vec![Inline::Strong(x())]);
+    }
+
+    #[test]
+    fn intraword_underscore() {
+        let str = |s: &str| Inline::Str(s.to_owned());
+        let emph = |s| Inline::Emph(vec![str(s)]);
+        let literals = ["foo_bar_baz", "5_000_000", "foo_bar_", "_foo_bar", "foo__bar__", "пр_иве_т"];
+        for literal in literals {
+            assert_eq!(parse(literal), vec![str(literal)]);
+        }
+        assert_eq!(parse("_foo_"), vec![emph("foo")]);
+        assert_eq!(parse("foo _bar_"), vec![str("foo"), Inline::Space, emph("bar")]);
+        assert_eq!(parse("_foo_bar_baz_"), vec![emph("foo_bar_baz")]);
+        assert_eq!(parse("*foo_bar*"), vec![emph("foo_bar")]);
+        assert_eq!(parse("foo*bar*baz"), vec![str("foo"), emph("bar"), str("baz")]);
+    }
+
+    #[test]
+    fn long_tilde_runs() {
+        let str = |s: &str| Inline::Str(s.to_owned());
+        assert_eq!(parse("~~~x~~~"), vec![str("~~~x~~~")]);
+        let spaced = vec![str("a"), Inline::Space, str("~~~"), Inline::Space, str("b")];
+        assert_eq!(parse("a ~~~ b"), spaced);
+        assert_eq!(parse("~~x~~"), vec![Inline::Strikeout(vec![str("x")])]);
+        assert_eq!(parse("~~~~x~~"), vec![str("~~~~x~~")]);
+    }
+
+    #[test]
+    fn normalized() {
+        let str = |s: &str| Inline::Str(s.to_owned());
+        let strong = |s| Inline::Strong(vec![str(s)]);
+        assert_eq!(parse("**a** **b**"), vec![strong("a"), Inline::Space, strong("b")]);
+        assert_eq!(parse("**a\\*b** *c**d*"), vec![
+            Inline::Strong(vec![str("a*b")]),
+            Inline::Space,
+            Inline::Emph(vec![str("c**d")]),
+        ]);
+    }
+
+    #[test]
+    fn html_modes() {
+        let html = |html| parse_with("<b>x</b>", MdReaderOptions { html, ..Default::default() });
+        let raw = |s: &str| Inline::RawInline(Format("html".to_owned()), s.to_owned());
+        assert_eq!(html(HtmlMode::Raw), vec![raw("<b>"), Inline::Str("x".to_owned()), raw("</b>")]);
+        assert_eq!(html(HtmlMode::Escape), vec![Inline::Str("<b>x</b>".to_owned())]);
+        assert_eq!(html(HtmlMode::Strip), vec![Inline::Str("x".to_owned())]);
+        let options = MdReaderOptions { html: HtmlMode::Raw, ..Default::default() };
+        assert_eq!(parse_with("a <u>*x*</u>", options), vec![
+            Inline::Str("a".to_owned()),
+            Inline::Space,
+            raw("<u>"),
+            Inline::Emph(vec![Inline::Str("x".to_owned())]),
+            raw("</u>"),
+        ]);
+    }
+
+    #[test]
+    fn html_comments() {
+        let str = |s: &str| Inline::Str(s.to_owned());
+        let comment = Inline::RawInline(Format("html".to_owned()), "<!-- c\nd -->".to_owned());
+        let keep = MdReaderOptions { keep_comments: true, ..Default::default() };
+        assert_eq!(parse("a<!-- c\nd -->b"), vec![str("ab")]);
+        assert_eq!(parse_with("a<!-- c\nd -->b", keep), vec![str("a"), comment, str("b")]);
+        let raw = MdReaderOptions { html: HtmlMode::Raw, ..Default::default() };
+        assert_eq!(parse_with("<!-- c -->", raw), Vec::new());
+        assert_eq!(parse("<!-- c"), vec![str("<!--"), Inline::Space, str("c")]);
+    }
+
+    #[test]
+    fn html_tags() {
+        let options =
+            MdReaderOptions { html: HtmlMode::Raw, keep_comments: true, ..Default::default() };
+        let raw = |s: &str| Inline::RawInline(Format("html".to_owned()), s.to_owned());
+        for tag in [
+            "<a href=\"x\" title='y' data-z=w\n hidden>",
+            "<br/>",
+            "<img src=a.png />",
+            "</span >",
+            "<!-- comment -->",
+            "<!-->",
+            "<?php x ?>",
+            "<!DOCTYPE html>",
+            "<![CDATA[ <x> ]]>",
+        ] {
+            assert_eq!(parse_with(tag, options), vec![raw(tag)], "{tag}");
+        }
+        for text in ["<33>", "<a h*=x>", "<a href=\"x>", "</a b>", "<a/ >", "a < b"] {
+            assert!(!parse_with(text, options).iter().any(|i| matches!(i, Inline::RawInline(..))));
+        }
+    }
+
+    #[test]
+    fn math() {
+        let options = MdReaderOptions { math: true, ..Default::default() };
+        let str = |s: &str| Inline::Str(s.to_owned());
+        let math = |t, s: &str| Inline::Math(t, s.to_owned());
+        assert_eq!(parse_with("cost is \\$5", options), vec![
+            str("cost"),
+            Inline::Space,
+            str("is"),
+            Inline::Space,
+            str("$5"),
+        ]);
+        assert_eq!(parse_with("$x$ and \\$y", options), vec![
+            math(MathType::InlineMath, "x"),
+            Inline::Space,
+            str("and"),
+            Inline::Space,
+            str("$y"),
+        ]);
+        assert_eq!(parse_with("$$\\frac{a}{b} \\$ $$", options), vec![math(
+            MathType::DisplayMath,
+            "\\frac{a}{b} \\$ "
+        )]);
+        assert_eq!(parse_with("$a*b$*c*", options), vec![
+            math(MathType::InlineMath, "a*b"),
+            Inline::Emph(vec![str("c")]),
+        ]);
+        for text in ["$5 and $6", "$ x$", "$x $", "$x$5"] {
+            assert!(!parse_with(text, options).iter().any(|i| matches!(i, Inline::Math(..))));
+        }
+        assert_eq!(parse("$x$"), vec![str("$x$")]);
+    }
 }
@@ -1,4 +1,5 @@
 use std::iter::{Peekable, Rev};
+use std::mem;
 use std::str::CharIndices;
 
 /// Represents the result after skipping indent
@@ -54,6 +55,7 @@ impl<'a> SkipIndent<'a> {
                 ' ' => total += 1,
                 '\t' => total = total + (4 - (total % 4)),
                 c => {
+                    debug_assert!(line.is_char_boundary(i));
                     return SkipIndentResult::Line(Self {
                         first: c,
                         indent: total - total_indent,
@@ -77,6 +79,7 @@ impl<'a> SkipIndent<'a> {
 
     /// Gets line without the first char
     pub fn get_rest(&self) -> &'a str {
+        debug_assert!(self.line.starts_with(self.first));
         // Safety: using utf8 length of first char as index
         unsafe { self.line.get_unchecked(self.first.len_utf8()..) }
     }
@@ -223,9 +226,11 @@ impl<'a> Iter<'a> {
         let mut escape = false;
         loop {
             match self.iter.next()? {
-                (end, current) if !escape && current == c =>
-                // Safety: start and end both from CharIndices
-                    return Some(unsafe { self.source.get_unchecked(start..end) }),
+                (end, current) if !escape && current == c => {
+                    debug_assert!(self.source.is_char_boundary(start) && start <= end);
+                    // Safety: start and end both from CharIndices
+                    return Some(unsafe { self.source.get_unchecked(start..end) });
+                },
                 (_, current) => escape = current == '\\' && !escape,
             }
         }
@@ -241,9 +246,11 @@ impl<'a> Iter<'a> {
         let mut escape = false;
         loop {
             match self.iter.next()? {
-                (end, current) if !escape && current == expected =>
-                // Safety: start and end both from CharIndices
-                    return Some(unsafe { self.source.get_unchecked(start..end) }),
+                (end, current) if !escape && current == expected => {
+                    debug_assert!(self.source.is_char_boundary(start) && start <= end);
+                    // Safety: start and end both from CharIndices
+                    return Some(unsafe { self.source.get_unchecked(start..end) });
+                },
                 (_, current) if !escape && current == illegal => return None,
                 (_, current) => escape = current == '\\' && !escape,
             }
@@ -258,9 +265,11 @@ impl<'a> Iter<'a> {
                 let mut escape = false;
                 loop {
                     match self.iter.next()? {
-                        (e, '>') if !escape =>
-                        // Safety: s and e both from CharIndices, char at s is '<' with width 1
-                            return Some(unsafe { self.source.get_unchecked((s + 1)..e) }),
+                        (e, '>') if !escape => {
+                            debug_assert!(self.source.is_char_boundary(s + 1) && s < e);
+                            // Safety: s and e both from CharIndices, char at s is '<' with width 1
+                            return Some(unsafe { self.source.get_unchecked((s + 1)..e) });
+                        },
                         (_, '\n') => return None,
                         (_, c) => escape = c == '\\' && !escape,
                     }
@@ -268,11 +277,16 @@ impl<'a> Iter<'a> {
             },
             (s, _) => loop {
                 match self.iter.peek() {
-                    Some(&(e, ' ' | '\t' | '\n')) =>
-                    // Safety: s and e both from CharIndices
-                        return Some(unsafe { self.source.get_unchecked(s..e) }),
-                    // Safety: s from CharIndices
-                    None => return Some(unsafe { self.source.get_unchecked(s..) }),
+                    Some(&(e, ' ' | '\t' | '\n')) => {
+                        debug_assert!(self.source.is_char_boundary(s) && s <= e);
+                        // Safety: s and e both from CharIndices
+                        return Some(unsafe { self.source.get_unchecked(s..e) });
+                    },
+                    None => {
+                        debug_assert!(self.source.is_char_boundary(s));
+                        // Safety: s from CharIndices
+                        return Some(unsafe { self.source.get_unchecked(s..) });
+                    },
                     Some((_, c)) if c.is_ascii_control() => return None,
                     Some(_) => _ = self.iter.next(),
                 }
@@ -280,11 +294,54 @@ impl<'a> Iter<'a> {
         }
     }
 
+    /// Skips until the end of an inline link destination not enclosed in `<>` and returns it. The
+    /// destination ends before whitespace or an unbalanced unescaped `')'`. Returns none if it
+    /// contains a control char
+    pub fn get_inline_link_destination(&mut self) -> Option<&'a str> {
+        let start = self.iter.peek().map_or(self.source.len(), |x| x.0);
+        let mut depth = 0usize;
+        let mut escape = false;
+        loop {
+            match self.iter.peek() {
+                Some(&(e, ' ' | '\t' | '\n')) => {
+                    debug_assert!(self.source.is_char_boundary(start) && start <= e);
+                    // Safety: start and e both from CharIndices
+                    return Some(unsafe { self.source.get_unchecked(start..e) });
+                },
+                Some(&(e, ')')) if !escape && depth == 0 => {
+                    debug_assert!(self.source.is_char_boundary(start) && start <= e);
+                    // Safety: start and e both from CharIndices
+                    return Some(unsafe { self.source.get_unchecked(start..e) });
+                },
+                None => {
+                    debug_assert!(self.source.is_char_boundary(start));
+                    // Safety: start from CharIndices or the length of the string
+                    return Some(unsafe { self.source.get_unchecked(start..) });
+                },
+                Some(&(_, c)) => {
+                    if c.is_ascii_control() {
+                        return None;
+                    }
+                    match c {
+                        '(' if !escape => depth += 1,
+                        ')' if !escape => depth -= 1,
+                        _ => {},
+                    }
+                    escape = c == '\\' && !escape;
+                    self.iter.next();
+                },
+            }
+        }
+    }
+
     /// Gets the rest of the slice
     pub fn get_str(&mut self) -> &'a str {
         match self.iter.peek() {
-            // Safety: index from CharIndices
-            Some(&(i, _)) => unsafe { self.source.get_unchecked(i..) },
+            Some(&(i, _)) => {
+                debug_assert!(self.source.is_char_boundary(i));
+                // Safety: index from CharIndices
+                unsafe { self.source.get_unchecked(i..) }
+            },
             None => "",
         }
     }
@@ -343,9 +400,11 @@ impl<'a> IndentIter<'a> {
     /// Skips indent from the rest of the iterator
     pub fn skip_indent(&mut self) -> SkipIndentResult<'a> {
         match self.iter.peek() {
-            Some(&(i, _)) =>
-            // Safety: index from CharIndices
-                SkipIndent::skip(unsafe { self.source.get_unchecked(i..) }, self.indent),
+            Some(&(i, _)) => {
+                debug_assert!(self.source.is_char_boundary(i));
+                // Safety: index from CharIndices
+                SkipIndent::skip(unsafe { self.source.get_unchecked(i..) }, self.indent)
+            },
             None => SkipIndentResult::Blank(0),
         }
     }
@@ -392,8 +451,11 @@ impl<'a> RevIter<'a> {
     /// Gets the rest of the slice
     fn get_str(&mut self) -> &str {
         match self.iter.peek() {
-            //Safety: index from CharIndices and width of the peeked char
-            Some(&(i, c)) => unsafe { self.source.get_unchecked(..i + c.len_utf8()) },
+            Some(&(i, c)) => {
+                debug_assert!(self.source.is_char_boundary(i + c.len_utf8()));
+                //Safety: index from CharIndices and width of the peeked char
+                unsafe { self.source.get_unchecked(..i + c.len_utf8()) }
+            },
             None => "",
         }
     }
@@ -414,6 +476,32 @@ impl<'a> RevIter<'a> {
     pub fn get_string(&mut self) -> String { self.get_str().to_owned() }
 }
 
+/// Iterator over lines of a string, treating `\n`, `\r\n` and a lone `\r` as line endings
+pub struct Lines<'a>(&'a str);
+
+impl<'a> Lines<'a> {
+    /// Creates a new iterator over lines of a given string slice
+    pub const fn new(source: &'a str) -> Self { Self(source) }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        match self.0.find(['\r', '\n']) {
+            Some(i) => {
+                let (line, rest) = self.0.split_at(i);
+                self.0 = rest.strip_prefix("\r\n").unwrap_or_else(|| &rest[1..]);
+                Some(line)
+            },
+            None => Some(mem::take(&mut self.0)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -443,4 +531,12 @@ mod tests {
         check_indent("  \t line", 1, 4, 5);
         check_indent("  \t line", 2, 7, 9);
     }
+
+    #[test]
+    fn test_lines() {
+        let lines: Vec<_> = Lines::new("a\nb\r\nc\rd\r\re\n").collect();
+        assert_eq!(lines, vec!["a", "b", "c", "d", "", "e"]);
+        assert_eq!(Lines::new("").count(), 0);
+        assert_eq!(Lines::new("\r\n").collect::<Vec<_>>(), vec![""]);
+    }
 }
@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+use crate::md_reader::inline_parser::InlineParser;
 
 /// Represents a link in a GitHub Flavoured Markdown document
 #[derive(Debug)]
@@ -10,19 +14,28 @@ pub struct Link {
 }
 
 impl Link {
-    /// Creates a new link by copying slices
+    /// Creates a new link by unescaping slices
     fn new(url: &str, title: Option<&str>) -> Self {
-        Self { url: url.to_owned(), title: title.map(str::to_owned) }
+        Self { url: InlineParser::unescape(url), title: title.map(InlineParser::unescape) }
     }
 }
 
 /// Represents links found in the document
 #[derive(Debug, Default)]
-pub struct Links(HashMap<String, Link>);
+pub struct Links {
+    /// Links by their stripped labels
+    definitions: HashMap<String, Link>,
+    /// Unstripped labels of the links in the order they were defined
+    labels: Vec<String>,
+    /// Stripped labels of the links which were referenced
+    used: RefCell<HashSet<String>>,
+    /// Unstripped labels of references without a matching link, in the order they were found
+    unresolved: RefCell<Vec<String>>,
+}
 
 impl Links {
     /// Creates a new empty collection of links
-    pub fn new() -> Self { Self(HashMap::new()) }
+    pub fn new() -> Self { Self::default() }
 
     /// Strips a key for matching or inserting
     pub fn strip(key: &str) -> String {
@@ -47,14 +60,50 @@ impl Links {
 
     /// Adds new link if not already present
     pub fn add_new(&mut self, unstripped: &str, destination: &str, title: Option<&str>) {
-        self.0.entry(Self::strip(unstripped)).or_insert_with(|| Link::new(destination, title));
+        if let Entry::Vacant(e) = self.definitions.entry(Self::strip(unstripped)) {
+            e.insert(Link::new(destination, title));
+            self.labels.push(unstripped.to_owned());
+        }
+    }
+
+    /// Adds links from another collection if not already present, they aren't reported by
+    /// [`Self::unused`]
+    pub fn merge(&mut self, other: Self) {
+        for (label, link) in other.definitions {
+            self.definitions.entry(label).or_insert(link);
+        }
+    }
+
+    /// Gets link from collection if present, marking it as used
+    pub fn get(&self, stripped: &str) -> Option<&Link> {
+        let link = self.definitions.get(stripped);
+        if link.is_some() {
+            self.used.borrow_mut().insert(stripped.to_owned());
+        }
+        link
+    }
+
+    /// Gets link referenced by an unstripped label, remembering the label if the link isn't present
+    pub fn resolve(&self, unstripped: &str) -> Option<&Link> {
+        let link = self.get(&Self::strip(unstripped));
+        if link.is_none() {
+            self.unresolved.borrow_mut().push(unstripped.to_owned());
+        }
+        link
     }
 
-    /// Gets link from collection if present
-    pub fn get(&self, stripped: &str) -> Option<&Link> { self.0.get(stripped) }
+    /// Returns labels of unresolved references found since the last call
+    pub fn take_unresolved(&self) -> Vec<String> { self.unresolved.take() }
+
+    /// Returns unstripped labels of links which were never referenced, in the order they were
+    /// defined
+    pub fn unused(&self) -> Vec<&str> {
+        let used = self.used.borrow();
+        self.labels.iter().filter(|l| !used.contains(&Self::strip(l))).map(String::as_str).collect()
+    }
 
     /// Returns amount of links in the collection
-    pub fn len(&self) -> usize { self.0.len() }
+    pub fn len(&self) -> usize { self.definitions.len() }
 }
 
 #[cfg(test)]
@@ -68,4 +117,26 @@ mod tests {
         assert_eq!(Links::strip(" \n both \n ").as_str(), "both");
         assert_eq!(Links::strip("  internal   \n   spaces \n ").as_str(), "internal spaces");
     }
+
+    #[test]
+    fn unresolved() {
+        let mut links = Links::new();
+        links.add_new("Foo", "/url", None);
+        assert!(links.resolve(" foo ").is_some());
+        assert!(links.resolve("bar").is_none());
+        assert!(links.resolve("Baz").is_none());
+        assert_eq!(links.take_unresolved(), vec!["bar", "Baz"]);
+        assert!(links.take_unresolved().is_empty());
+    }
+
+    #[test]
+    fn unused() {
+        let mut links = Links::new();
+        links.add_new("Used", "/url", None);
+        links.add_new("Unused", "/url", None);
+        links.add_new("used", "/other", None);
+        assert_eq!(links.unused(), vec!["Used", "Unused"]);
+        assert!(links.resolve("USED").is_some());
+        assert_eq!(links.unused(), vec!["Unused"]);
+    }
 }
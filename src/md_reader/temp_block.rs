@@ -3,6 +3,8 @@ use derive_more::From;
 use atx_heading::AtxHeading;
 use block_quote::BlockQuote;
 use fenced_code_block::FencedCodeBlock;
+use html_block::HtmlBlock;
+use html_comment::HtmlComment;
 use indented_code_block::IndentedCodeBlock;
 use list::{CheckOrSetextResult, List};
 use paragraph::Paragraph;
@@ -11,11 +13,13 @@ use thematic_break::ThematicBreak;
 
 use crate::ast::Block;
 use crate::md_reader::iters::{SkipIndent, SkipIndentResult};
-use crate::md_reader::Links;
+use crate::md_reader::{HtmlMode, Links, MdReaderOptions, Warning};
 
 mod atx_heading;
 mod block_quote;
 mod fenced_code_block;
+mod html_block;
+mod html_comment;
 mod indented_code_block;
 mod list;
 mod paragraph;
@@ -32,23 +36,38 @@ pub enum TempBlock {
     ThematicBreak(ThematicBreak),
     IndentedCodeBlock(IndentedCodeBlock),
     FencedCodeBlock(FencedCodeBlock),
+    HtmlComment(HtmlComment),
+    HtmlBlock(HtmlBlock),
     Table(Table),
     BlockQuote(BlockQuote),
     List(List),
 }
 
 impl TempBlock {
-    /// Parses next line of a document, pushing finished blocks into the `finished` argument and
-    /// finished links into the `links` argument
-    pub fn next_str(&mut self, line: &str, finished: &mut Vec<Self>, links: &mut Links) {
-        self.next(SkipIndent::skip(line, 0), finished, links);
+    /// Parses next line of a document, pushing finished blocks into the `finished` argument,
+    /// finished links into the `links` argument and the lines on which the finished blocks started
+    /// into the `starts` argument
+    pub fn next_str(
+        &mut self, line: &str, finished: &mut Vec<Self>, starts: &mut LineStarts,
+        links: &mut Links, options: MdReaderOptions,
+    ) {
+        starts.line += 1;
+        let result = match SkipIndent::skip(line, 0) {
+            SkipIndentResult::Line(line) => self.next_line(line, links, options),
+            SkipIndentResult::Blank(i) => self.next_blank(i, links).0,
+        };
+        starts.apply(&result, self.is_empty());
+        self.apply_result(result, finished, links);
     }
 
     /// Parses next line of a document after skipping indent pushing finished blocks into the
     /// `finished` argument and finished links into the `links` argument
-    fn next(&mut self, line: SkipIndentResult, finished: &mut Vec<Self>, links: &mut Links) {
+    fn next(
+        &mut self, line: SkipIndentResult, finished: &mut Vec<Self>, links: &mut Links,
+        options: MdReaderOptions,
+    ) {
         let result = match line {
-            SkipIndentResult::Line(line) => self.next_line(line, links),
+            SkipIndentResult::Line(line) => self.next_line(line, links, options),
             SkipIndentResult::Blank(i) => self.next_blank(i, links).0,
         };
         self.apply_result(result, finished, links);
@@ -59,15 +78,19 @@ impl TempBlock {
     /// # Panics
     /// If the block is [`Self::AtxHeading`] or [`Self::ThematicBreak`] which are always passed
     /// as finished
-    fn next_line(&mut self, line: SkipIndent, links: &mut Links) -> LineResult {
+    fn next_line(
+        &mut self, line: SkipIndent, links: &mut Links, options: MdReaderOptions,
+    ) -> LineResult {
         match self {
-            Self::Empty => Self::empty_next_line(line),
-            Self::Paragraph(p) => p.next(line),
-            Self::IndentedCodeBlock(i) => i.next(line),
+            Self::Empty => Self::empty_next_line(line, options),
+            Self::Paragraph(p) => p.next(line, options),
+            Self::IndentedCodeBlock(i) => i.next(line, options),
             Self::FencedCodeBlock(f) => f.next(line),
-            Self::Table(t) => t.next(line),
-            Self::BlockQuote(b) => b.next(line, links),
-            Self::List(l) => l.next(line, links),
+            Self::HtmlComment(h) => h.next(&line),
+            Self::HtmlBlock(h) => h.next(&line),
+            Self::Table(t) => t.next(line, options),
+            Self::BlockQuote(b) => b.next(line, links, options),
+            Self::List(l) => l.next(line, links, options),
             Self::AtxHeading(_) | Self::ThematicBreak(_) => unreachable!(),
         }
     }
@@ -84,8 +107,11 @@ impl TempBlock {
             Self::Empty => return (LineResult::None, true),
             Self::Paragraph(_) | Self::Table(_) | Self::BlockQuote(_) =>
                 return (LineResult::DoneSelf, true),
+            Self::HtmlBlock(h) if h.ends_with_blank() => return (LineResult::DoneSelf, true),
             Self::IndentedCodeBlock(i) => i.push_blank(indent),
             Self::FencedCodeBlock(f) => f.push_blank(indent),
+            Self::HtmlComment(h) => h.push_blank(),
+            Self::HtmlBlock(h) => h.push_blank(),
             Self::List(l) => l.next_blank(indent, links),
             Self::AtxHeading(_) | Self::ThematicBreak(_) => unreachable!(),
         }
@@ -96,12 +122,13 @@ impl TempBlock {
     /// `links` argument. Returns a [`LineResult`] as a result. Used by [`BlockQuote`] when the line
     /// is missing the `'>'` char or by [`List`] when the line isn't indented enough but in both
     /// cases only if the line is indented by at most 3 spaces
-    fn next_continuation(&mut self, line: SkipIndent) -> LineResult {
+    fn next_continuation(&mut self, line: SkipIndent, options: MdReaderOptions) -> LineResult {
         match self {
-            Self::Paragraph(p) => p.next_continuation(line),
-            Self::BlockQuote(b) => b.current.next_continuation(line),
-            Self::List(List { current: Some(c), .. }) => c.current.next_continuation(line),
-            _ => Self::check_block_known_indent(line).into_line_result_paragraph(true),
+            Self::Paragraph(p) => p.next_continuation(line, options),
+            Self::BlockQuote(b) => b.current.next_continuation(line, options),
+            Self::List(List { current: Some(c), .. }) =>
+                c.current.next_continuation(line, options),
+            _ => Self::check_block_known_indent(line, options).into_line_result_paragraph(true),
         }
     }
 
@@ -174,29 +201,48 @@ impl TempBlock {
         }
     }
 
-    /// Finishes block into a [`Block`]
-    pub fn finish(self, links: &Links) -> Option<Block> {
+    /// Finishes block into a [`Block`] pushing found problems into the `warnings` argument
+    pub fn finish(
+        self, links: &Links, options: MdReaderOptions, warnings: &mut Vec<Warning>,
+    ) -> Option<Block> {
         match self {
             Self::Empty => None,
-            Self::Paragraph(p) => p.finish(links),
-            Self::AtxHeading(a) => Some(a.finish(links)),
+            Self::Paragraph(p) => p.finish(links, options),
+            Self::AtxHeading(a) => Some(a.finish(links, options)),
             Self::ThematicBreak(_) => Some(ThematicBreak::finish()),
             Self::IndentedCodeBlock(i) => Some(i.finish()),
             Self::FencedCodeBlock(c) => Some(c.finish()),
-            Self::Table(t) => Some(t.finish(links)),
-            Self::BlockQuote(b) => Some(b.finish(links)),
-            Self::List(l) => Some(l.finish(links)),
+            Self::HtmlComment(h) => h.finish(options),
+            Self::HtmlBlock(h) => h.finish(options),
+            Self::Table(t) => Some(t.finish(links, options, warnings)),
+            Self::BlockQuote(b) => Some(b.finish(links, options, warnings)),
+            Self::List(l) => Some(l.finish(links, options, warnings)),
+        }
+    }
+
+    /// Merges consecutive [`Block::DefinitionList`] elements, which are parsed as separate [`List`]
+    /// blocks for each term
+    pub fn merge_definitions<I>(blocks: I) -> Vec<Block>
+    where I: IntoIterator<Item = Block> {
+        let mut result = Vec::new();
+        for b in blocks {
+            match (result.last_mut(), b) {
+                (Some(Block::DefinitionList(items)), Block::DefinitionList(new)) =>
+                    items.extend(new),
+                (_, b) => result.push(b),
+            }
         }
+        result
     }
 
     /// Creates a new block from a line after skipping indent. Used by [`BlockQuote`] when creating
     /// the first block. Returns current block and finished blocks
-    fn new_empty(line: SkipIndentResult) -> (Self, Vec<Self>) {
+    fn new_empty(line: SkipIndentResult, options: MdReaderOptions) -> (Self, Vec<Self>) {
         match line {
             SkipIndentResult::Line(line) => {
                 let mut new = Self::Empty;
                 let mut finished = Vec::new();
-                new.apply_result_no_links(Self::empty_next_line(line), &mut finished);
+                new.apply_result_no_links(Self::empty_next_line(line, options), &mut finished);
                 (new, finished)
             },
             SkipIndentResult::Blank(_) => (Self::Empty, Vec::new()),
@@ -205,45 +251,52 @@ impl TempBlock {
 
     /// Creates a new block from a non-blank line after skipping indent. Used by [`List`] when
     /// creating the first block. Returns current block and finished blocks
-    fn new_empty_known_indent(line: SkipIndent) -> (Self, Vec<Self>) {
+    fn new_empty_known_indent(line: SkipIndent, options: MdReaderOptions) -> (Self, Vec<Self>) {
         let mut new = Self::Empty;
         let mut finished = Vec::new();
-        new.apply_result_no_links(Self::empty_next_line_known_indent(line), &mut finished);
+        let result = Self::empty_next_line_known_indent(line, options);
+        new.apply_result_no_links(result, &mut finished);
         (new, finished)
     }
 
     /// Checks if a new block can be started from a non-blank line. Returns a [`CheckResult`]
-    fn check_block(line: SkipIndent) -> CheckResult {
+    fn check_block(line: SkipIndent, options: MdReaderOptions) -> CheckResult {
         match line.indent {
-            0..=3 => Self::check_block_known_indent(line),
+            0..=3 => Self::check_block_known_indent(line, options),
             4.. => CheckResult::New(IndentedCodeBlock::new(line).into()),
         }
     }
 
     /// Checks if a new block can be started from a non-blank line assuming the indent is at most 3
-    /// spaces. Returns a [`CheckResult`]
-    fn check_block_known_indent(line: SkipIndent) -> CheckResult {
+    /// spaces. Returns a [`CheckResult`]. HTML blocks other than comments are not started in
+    /// [`HtmlMode::Escape`]
+    fn check_block_known_indent(line: SkipIndent, options: MdReaderOptions) -> CheckResult {
         match line.first {
             '#' => AtxHeading::check(line),
             '_' => ThematicBreak::check(line),
             '~' | '`' => FencedCodeBlock::check(line),
-            '>' => CheckResult::New(BlockQuote::new(&line).into()),
-            '*' | '-' => List::check_star_dash(line),
-            '+' => List::check_plus(line),
-            '0'..='9' => List::check_number(line),
+            '<' => match HtmlComment::check(line) {
+                CheckResult::Text(line) if options.html != HtmlMode::Escape =>
+                    HtmlBlock::check(line, false),
+                c => c,
+            },
+            '>' => CheckResult::New(BlockQuote::new(&line, options).into()),
+            '*' | '-' => List::check_star_dash(line, options),
+            '+' => List::check_plus(line, options),
+            '0'..='9' => List::check_number(line, options),
             _ => CheckResult::Text(line),
         }
     }
 
     /// Parses next non-blank line of the document when the current block is [`Self::Empty`]
-    fn empty_next_line(line: SkipIndent) -> LineResult {
-        Self::check_block(line).into_line_result_paragraph(false)
+    fn empty_next_line(line: SkipIndent, options: MdReaderOptions) -> LineResult {
+        Self::check_block(line, options).into_line_result_paragraph(false)
     }
 
     /// Parses next non-blank line indented of the document when it's indented at most 3 spaces and
     /// the current block is [`Self::Empty`]
-    fn empty_next_line_known_indent(line: SkipIndent) -> LineResult {
-        Self::check_block_known_indent(line).into_line_result_paragraph(false)
+    fn empty_next_line_known_indent(line: SkipIndent, options: MdReaderOptions) -> LineResult {
+        Self::check_block_known_indent(line, options).into_line_result_paragraph(false)
     }
 
     /// Replaces self with the default value ([`Self::Empty`]), returning the previous value
@@ -266,6 +319,39 @@ impl TempBlock {
     }
 }
 
+/// Line numbers on which the top level blocks of a document started, counted from 1
+#[derive(Debug, Default)]
+pub struct LineStarts {
+    /// Number of the last parsed line
+    line: usize,
+    /// Line on which the current block started
+    pub current: usize,
+    /// Lines on which the finished blocks started, in the order of the finished blocks
+    pub finished: Vec<usize>,
+}
+
+impl LineStarts {
+    /// Records the start lines of blocks changed by a [`LineResult`] of the last parsed line
+    fn apply(&mut self, result: &LineResult, empty: bool) {
+        match result {
+            LineResult::New(_) if empty => self.current = self.line,
+            LineResult::None | LineResult::New(_) => {},
+            LineResult::DoneSelf => self.finished.push(self.current),
+            LineResult::Done(_) => self.finished.push(self.line),
+            LineResult::DoneSelfAndNew(new) => {
+                self.finished.push(self.current);
+                // A table split from a paragraph starts with the header on the previous line
+                self.current = if matches!(new, TempBlock::Table(_)) {
+                    self.line - 1
+                } else {
+                    self.line
+                };
+            },
+            LineResult::DoneSelfAndOther(_) => self.finished.extend([self.current, self.line]),
+        }
+    }
+}
+
 /// Enum representing every possible result after parsing a line of a document
 pub enum LineResult {
     /// Line was consumed and nothing changed
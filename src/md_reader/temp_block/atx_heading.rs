@@ -1,7 +1,7 @@
 use crate::ast::Block;
 use crate::md_reader::inline_parser::InlineParser;
 use crate::md_reader::iters::SkipIndent;
-use crate::md_reader::Links;
+use crate::md_reader::{Links, MdReaderOptions};
 use crate::md_reader::temp_block::CheckResult;
 
 /// Struct representing a finished atx heading
@@ -39,8 +39,8 @@ impl AtxHeading {
     }
 
     /// Finishes a heading into a [`Block`] by parsing the content
-    pub fn finish(self, links: &Links) -> Block {
-        Block::new_header(self.level, InlineParser::parse_lines(&self.content, links))
+    pub fn finish(self, links: &Links, options: MdReaderOptions) -> Block {
+        Block::new_header(self.level, InlineParser::parse_lines(&self.content, links, options))
     }
 }
 
@@ -83,6 +83,9 @@ mod tests {
         assert_done("#");
         assert_text("#foo");
         assert_done("#                              foo");
+        assert_done("#\tfoo");
+        assert_equals("##\tfoo\t#", "foo");
+        assert_equals("#\tfoo #\t", "foo");
     }
 
     #[test]
@@ -92,5 +95,9 @@ mod tests {
         assert_equals("# foo ##################", "foo");
         assert_equals("# #", "");
         assert_equals("# foo #    \t    ", "foo");
+        assert_equals("# a # b #", "a # b");
+        assert_equals("# foo # bar #", "foo # bar");
+        assert_equals("# foo # bar", "foo # bar");
+        assert_equals("### foo ### b", "foo ### b");
     }
 }
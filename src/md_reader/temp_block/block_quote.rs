@@ -1,7 +1,8 @@
 use std::iter;
 
-use crate::ast::Block;
+use crate::ast::{attr_empty, Block, Inline};
 use crate::md_reader::iters::SkipIndent;
+use crate::md_reader::{MdReaderOptions, Warning};
 use crate::md_reader::temp_block::{LineResult, Links, TempBlock};
 
 /// Struct representing an unfinished block quote
@@ -15,38 +16,75 @@ pub struct BlockQuote {
 
 impl BlockQuote {
     /// Creates a block quote from a given non-blank line
-    pub fn new(line: &SkipIndent) -> Self {
+    pub fn new(line: &SkipIndent, options: MdReaderOptions) -> Self {
         let mut content = line.skip_indent_rest();
         content.inspect_line(|l| l.indent = l.indent.saturating_sub(1));
-        let (current, finished) = TempBlock::new_empty(content);
+        let (current, finished) = TempBlock::new_empty(content, options);
         Self { current: Box::new(current), finished }
     }
 
     /// Parses next non-blank line of a document
-    pub fn next(&mut self, line: SkipIndent, links: &mut Links) -> LineResult {
+    pub fn next(
+        &mut self, line: SkipIndent, links: &mut Links, options: MdReaderOptions,
+    ) -> LineResult {
         match line.indent {
             0..=3 =>
                 if line.first == '>' {
                     let mut content = line.skip_indent_rest();
                     content.inspect_line(|l| l.indent = l.indent.saturating_sub(1));
-                    self.current.next(content, &mut self.finished, links);
+                    self.current.next(content, &mut self.finished, links, options);
                     LineResult::None
                 } else {
-                    self.current.next_continuation(line)
+                    self.current.next_continuation(line, options)
                 },
             4.. => self.current.next_indented_continuation(line),
         }
     }
 
     /// Finishes the block quote into a [`Block`]
-    pub fn finish(self, links: &Links) -> Block {
-        Block::BlockQuote(
+    pub fn finish(
+        self, links: &Links, options: MdReaderOptions, warnings: &mut Vec<Warning>,
+    ) -> Block {
+        let blocks = TempBlock::merge_definitions(
             self.finished
                 .into_iter()
                 .chain(iter::once(*self.current))
-                .filter_map(|t| t.finish(links))
-                .collect(),
-        )
+                .filter_map(|t| t.finish(links, options, warnings)),
+        );
+        if options.alerts {
+            Self::alert(blocks)
+        } else {
+            Block::BlockQuote(blocks)
+        }
+    }
+
+    /// Converts blocks of a block quote into a [`Block::Div`] if the first line is a GitHub alert
+    /// marker or into a [`Block::BlockQuote`] otherwise
+    fn alert(mut blocks: Vec<Block>) -> Block {
+        const ALERTS: [&str; 5] = ["Note", "Tip", "Important", "Warning", "Caution"];
+        let Some(Block::Para(content)) = blocks.first_mut() else {
+            return Block::BlockQuote(blocks);
+        };
+        let alert = match content.as_slice() {
+            [Inline::Str(s), ..]
+                if matches!(content.get(1), None | Some(Inline::SoftBreak | Inline::LineBreak)) =>
+                s.strip_prefix("[!").and_then(|s| s.strip_suffix(']')).and_then(|s| {
+                    ALERTS.into_iter().find(|a| a.eq_ignore_ascii_case(s))
+                }),
+            _ => None,
+        };
+        let Some(alert) = alert else {
+            return Block::BlockQuote(blocks);
+        };
+        content.drain(..content.len().min(2));
+        if content.is_empty() {
+            blocks.remove(0);
+        }
+        let title = (String::new(), vec![String::from("title")], Vec::new());
+        let title = Block::Div(title, vec![Block::Para(vec![Inline::Str(alert.into())])]);
+        let mut attr = attr_empty();
+        attr.1.push(alert.to_lowercase());
+        Block::Div(attr, iter::once(title).chain(blocks).collect())
     }
 }
 
@@ -54,10 +92,16 @@ impl BlockQuote {
 mod tests {
     use super::*;
 
-    fn new(line: &str) -> BlockQuote { BlockQuote::new(&SkipIndent::skip(line, 0).into_line()) }
+    fn new(line: &str) -> BlockQuote {
+        BlockQuote::new(&SkipIndent::skip(line, 0).into_line(), MdReaderOptions::default())
+    }
 
     fn next(block_quote: &mut BlockQuote, line: &str) -> LineResult {
-        block_quote.next(SkipIndent::skip(line, 0).into_line(), &mut Links::new())
+        block_quote.next(
+            SkipIndent::skip(line, 0).into_line(),
+            &mut Links::new(),
+            MdReaderOptions::default(),
+        )
     }
 
     fn assert_consumed(block_quote: &mut BlockQuote, line: &str) {
@@ -99,4 +143,32 @@ mod tests {
         assert_consumed(&mut block, ">> next");
         assert_consumed(&mut block, "> next");
     }
+
+    #[test]
+    fn alerts() {
+        let finish = |lines: &[&str], alerts| {
+            let mut block = new(lines[0]);
+            for line in &lines[1..] {
+                assert_consumed(&mut block, line);
+            }
+            let options = MdReaderOptions { alerts, ..Default::default() };
+            block.finish(&Links::new(), options, &mut Vec::new())
+        };
+        let str = |s: &str| Inline::Str(s.into());
+        let div = |class: &str, blocks| {
+            Block::Div((String::new(), vec![String::from(class)], Vec::new()), blocks)
+        };
+        let text = Block::Para(vec![str("text")]);
+        assert_eq!(
+            finish(&["> [!TIP]", "> text"], true),
+            div("tip", vec![div("title", vec![Block::Para(vec![str("Tip")])]), text.clone()])
+        );
+        assert_eq!(
+            finish(&["> [!warning]", ">", "> text"], true),
+            div("warning", vec![div("title", vec![Block::Para(vec![str("Warning")])]), text])
+        );
+        assert!(matches!(finish(&["> [!TIP]", "> text"], false), Block::BlockQuote(_)));
+        assert!(matches!(finish(&["> [!TIP] text"], true), Block::BlockQuote(_)));
+        assert!(matches!(finish(&["> [!OTHER]", "> text"], true), Block::BlockQuote(_)));
+    }
 }
@@ -160,10 +160,21 @@ mod tests {
         assert_closes("```", "``````");
         assert_closes("  ```", "```");
         assert_closes("  ```", "   ```");
+        assert_closes("   ```", "```");
+        assert_closes("   ~~~", "~~~");
         assert_consumes("  ```", "    ```");
         assert_consumes("~~~", "~~~ ~~~");
         assert_consumes("~~~", "~~~ abc");
     }
+
+    #[test]
+    fn closing_length() {
+        assert_consumes("```", "``");
+        assert_consumes("~~~", "~~");
+        assert_consumes("````", "```");
+        assert_closes("```", "````");
+        assert_closes("~~~", "~~~~~");
+    }
     
     #[test]
     fn indent() {
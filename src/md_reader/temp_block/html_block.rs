@@ -0,0 +1,154 @@
+use crate::ast::{Block, Format};
+use crate::md_reader::inline_parser::InlineParser;
+use crate::md_reader::iters::SkipIndent;
+use crate::md_reader::temp_block::{CheckResult, LineResult};
+use crate::md_reader::{HtmlMode, MdReaderOptions};
+
+/// Tags starting an HTML block which can contain blank lines
+const RAW_TAGS: [&str; 4] = ["script", "pre", "style", "textarea"];
+
+/// Closing tags ending an HTML block started by one of [`RAW_TAGS`]
+const RAW_END: [&str; 4] = ["</script>", "</pre>", "</style>", "</textarea>"];
+
+/// Tags starting an HTML block ended by a blank line
+const BLOCK_TAGS: [&str; 62] = [
+    "address", "article", "aside", "base", "basefont", "blockquote", "body", "caption", "center",
+    "col", "colgroup", "dd", "details", "dialog", "dir", "div", "dl", "dt", "fieldset",
+    "figcaption", "figure", "footer", "form", "frame", "frameset", "h1", "h2", "h3", "h4", "h5",
+    "h6", "head", "header", "hr", "html", "iframe", "legend", "li", "link", "main", "menu",
+    "menuitem", "nav", "noframes", "ol", "optgroup", "option", "p", "param", "search", "section",
+    "summary", "table", "tbody", "td", "tfoot", "th", "thead", "title", "tr", "track", "ul",
+];
+
+/// Struct representing an unfinished HTML block other than a comment, started by a line beginning
+/// with an HTML tag, processing instruction, declaration or CDATA section
+#[derive(Debug)]
+pub struct HtmlBlock {
+    /// Content of the block
+    content: String,
+    /// Strings ending the block when contained in a line, the block is ended by a blank line if
+    /// empty
+    end: &'static [&'static str],
+}
+
+impl HtmlBlock {
+    /// Checks if the line is beginning an HTML block assuming the first char was a `'<'`. A block
+    /// started by any complete tag followed only by whitespace can't interrupt a paragraph
+    pub fn check(line: SkipIndent, interrupt: bool) -> CheckResult {
+        let rest = &line.line[1..];
+        let lowercase = rest.to_ascii_lowercase();
+        let tag = |tags: &[&str], closing: bool| {
+            let name = if closing { lowercase.strip_prefix('/') } else { Some(lowercase.as_str()) };
+            name.is_some_and(|n| {
+                tags.iter().any(|t| {
+                    n.strip_prefix(t).is_some_and(|r| {
+                        r.is_empty() || r.starts_with([' ', '\t', '>']) || r.starts_with("/>")
+                    })
+                })
+            })
+        };
+        let (end, opener_length): (&[&str], _) = if tag(&RAW_TAGS, false) {
+            (&RAW_END, 1)
+        } else if rest.starts_with('?') {
+            (&["?>"], 2)
+        } else if rest.starts_with("![CDATA[") {
+            (&["]]>"], 9)
+        } else if rest
+            .strip_prefix('!')
+            .is_some_and(|r| r.starts_with(|c: char| c.is_ascii_alphabetic()))
+        {
+            (&[">"], 2)
+        } else if tag(&BLOCK_TAGS, false)
+            || tag(&BLOCK_TAGS, true)
+            || !interrupt
+                && InlineParser::html_length(line.line.trim_end())
+                    == Some(line.line.trim_end().len())
+        {
+            (&[], 0)
+        } else {
+            return CheckResult::Text(line);
+        };
+        let mut content = String::new();
+        line.push_full(&mut content);
+        let new = Self { content, end };
+        if new.is_end(&line.line[opener_length..]) {
+            CheckResult::Done(new.into())
+        } else {
+            CheckResult::New(new.into())
+        }
+    }
+
+    /// Parses next non-blank line of a document
+    pub fn next(&mut self, line: &SkipIndent) -> LineResult {
+        self.content.push('\n');
+        line.push_full(&mut self.content);
+        if self.is_end(line.line) {
+            LineResult::DoneSelf
+        } else {
+            LineResult::None
+        }
+    }
+
+    /// Returns whether the block is ended by a blank line
+    pub const fn ends_with_blank(&self) -> bool { self.end.is_empty() }
+
+    /// Pushes a blank line
+    pub fn push_blank(&mut self) { self.content.push('\n'); }
+
+    /// Finishes the block into a [`Block::RawBlock`] or drops it for [`HtmlMode::Strip`]. HTML
+    /// blocks are never started in [`HtmlMode::Escape`]
+    pub fn finish(self, options: MdReaderOptions) -> Option<Block> {
+        (options.html != HtmlMode::Strip)
+            .then(|| Block::RawBlock(Format(String::from("html")), self.content))
+    }
+
+    /// Checks if a line contains any of the strings ending the block
+    fn is_end(&self, line: &str) -> bool {
+        let lowercase = line.to_ascii_lowercase();
+        self.end.iter().any(|e| lowercase.contains(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::md_reader::temp_block::TempBlock;
+    use super::*;
+
+    fn check(line: &str, interrupt: bool) -> CheckResult<'_> {
+        HtmlBlock::check(SkipIndent::skip(line, 0).into_line(), interrupt)
+    }
+
+    #[test]
+    fn opening() {
+        let new = ["<div>", "<DIV class=\"a\">", "</table>", "<hr/>", "<pre>", "<?x", "<!DOCTYPE"];
+        for line in new {
+            assert!(matches!(check(line, true), CheckResult::New(_)), "{line}");
+        }
+        for line in ["<pre>x</pre>", "<?php ?>", "<!DOCTYPE html>", "<![CDATA[x]]>"] {
+            assert!(matches!(check(line, true), CheckResult::Done(_)), "{line}");
+        }
+        assert!(matches!(check("<a href=\"x\">", false), CheckResult::New(_)));
+        assert!(matches!(check("<a href=\"x\">", true), CheckResult::Text(_)));
+        assert!(matches!(check("<a href=\"x\"> text", false), CheckResult::Text(_)));
+        assert!(matches!(check("<divx>", true), CheckResult::Text(_)));
+        assert!(matches!(check("<", false), CheckResult::Text(_)));
+    }
+
+    #[test]
+    fn closing() {
+        let next = |h: &mut HtmlBlock, line| h.next(&SkipIndent::skip(line, 0).into_line());
+        let CheckResult::New(TempBlock::HtmlBlock(mut block)) = check("<script>", false) else {
+            panic!()
+        };
+        assert!(!block.ends_with_blank());
+        assert!(matches!(next(&mut block, "x"), LineResult::None));
+        block.push_blank();
+        assert!(matches!(next(&mut block, "</SCRIPT> after"), LineResult::DoneSelf));
+        assert_eq!(block.content, "<script>\nx\n\n</SCRIPT> after");
+        let CheckResult::New(TempBlock::HtmlBlock(mut block)) = check("<div>", false) else {
+            panic!()
+        };
+        assert!(block.ends_with_blank());
+        assert!(matches!(next(&mut block, "</div>"), LineResult::None));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::ast::{Block, Format};
+use crate::md_reader::iters::{SkipIndent, SkipIndentResult};
+use crate::md_reader::temp_block::{CheckResult, LineResult, Paragraph};
+use crate::md_reader::MdReaderOptions;
+
+/// Struct representing an unfinished HTML comment block, started by a line beginning with `<!--`
+/// and ended by a line containing `-->`
+#[derive(Debug)]
+pub struct HtmlComment {
+    /// Content including the comment delimiters
+    content: String,
+}
+
+impl HtmlComment {
+    /// Checks if the line is beginning an HTML comment block assuming the first char was a `'<'`. A
+    /// comment ended on the same line and followed by text is left to be parsed as a paragraph
+    /// containing an inline comment
+    pub fn check(line: SkipIndent) -> CheckResult {
+        let Some(rest) = line.line.strip_prefix("<!--") else {
+            return CheckResult::Text(line);
+        };
+        // Comments `<!-->` and `<!--->` end immediately after being opened
+        let end = match rest.find("-->") {
+            _ if rest.starts_with('>') => Some(5),
+            _ if rest.starts_with("->") => Some(6),
+            Some(i) => Some(i + 7),
+            None => None,
+        };
+        if end.is_some_and(|e| !line.line[e..].trim().is_empty()) {
+            return CheckResult::Text(line);
+        }
+        let mut content = String::new();
+        line.push_full(&mut content);
+        let new = Self { content };
+        if end.is_some() {
+            CheckResult::Done(new.into())
+        } else {
+            CheckResult::New(new.into())
+        }
+    }
+
+    /// Parses next non-blank line of a document. The comment ends after the first `-->` and the
+    /// rest of the line starts a new paragraph
+    pub fn next(&mut self, line: &SkipIndent) -> LineResult {
+        self.content.push('\n');
+        let Some(i) = line.line.find("-->") else {
+            line.push_full(&mut self.content);
+            return LineResult::None;
+        };
+        let (comment, rest) = line.line.split_at(i + 3);
+        self.content.push_str(&" ".repeat(line.indent));
+        self.content.push_str(comment);
+        match SkipIndent::skip(rest, 0) {
+            SkipIndentResult::Line(rest) =>
+                LineResult::DoneSelfAndNew(Paragraph::new(&rest).into()),
+            SkipIndentResult::Blank(_) => LineResult::DoneSelf,
+        }
+    }
+
+    /// Pushes a blank line
+    pub fn push_blank(&mut self) { self.content.push('\n'); }
+
+    /// Finishes the comment into a [`Block::RawBlock`] if [`MdReaderOptions::keep_comments`] is
+    /// set, otherwise the comment is dropped
+    pub fn finish(self, options: MdReaderOptions) -> Option<Block> {
+        options.keep_comments.then(|| Block::RawBlock(Format(String::from("html")), self.content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::md_reader::temp_block::TempBlock;
+    use super::*;
+
+    fn check(line: &str) -> CheckResult<'_> {
+        HtmlComment::check(SkipIndent::skip(line, 0).into_line())
+    }
+
+    #[test]
+    fn opening() {
+        assert!(matches!(check("<!-- comment"), CheckResult::New(_)));
+        assert!(matches!(check("<!-- comment -->"), CheckResult::Done(_)));
+        assert!(matches!(check("<!---->"), CheckResult::Done(_)));
+        assert!(matches!(check("<!-->"), CheckResult::Done(_)));
+        assert!(matches!(check("<!--->"), CheckResult::Done(_)));
+        assert!(matches!(check("<!-- comment -->  "), CheckResult::Done(_)));
+        assert!(matches!(check("<!-- comment --> text"), CheckResult::Text(_)));
+        assert!(matches!(check("<!--> text"), CheckResult::Text(_)));
+        assert!(matches!(check("<!- comment"), CheckResult::Text(_)));
+        assert!(matches!(check("<div>"), CheckResult::Text(_)));
+    }
+
+    #[test]
+    fn closing() {
+        let CheckResult::New(TempBlock::HtmlComment(mut comment)) = check("<!--") else {
+            panic!()
+        };
+        let next = |c: &mut HtmlComment, line| c.next(&SkipIndent::skip(line, 0).into_line());
+        assert!(matches!(next(&mut comment, "text"), LineResult::None));
+        assert!(matches!(next(&mut comment, "> quote"), LineResult::None));
+        comment.push_blank();
+        assert!(matches!(next(&mut comment, "  --> after"), LineResult::DoneSelfAndNew(_)));
+        assert_eq!(comment.content, "<!--\ntext\n> quote\n\n  -->");
+        let CheckResult::New(TempBlock::HtmlComment(mut comment)) = check("<!--") else {
+            panic!()
+        };
+        assert!(matches!(next(&mut comment, "--> "), LineResult::DoneSelf));
+    }
+}
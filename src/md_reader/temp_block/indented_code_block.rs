@@ -1,5 +1,6 @@
 use crate::ast::{attr_empty, Block};
 use crate::md_reader::iters::SkipIndent;
+use crate::md_reader::MdReaderOptions;
 use crate::md_reader::temp_block::{LineResult, TempBlock};
 
 /// Struct representing an unfinished code block
@@ -24,9 +25,10 @@ impl IndentedCodeBlock {
     }
 
     /// Parses next non-blank line of a document
-    pub fn next(&mut self, line: SkipIndent) -> LineResult {
+    pub fn next(&mut self, line: SkipIndent, options: MdReaderOptions) -> LineResult {
         match line.indent {
-            0..=3 => TempBlock::check_block_known_indent(line).into_line_result_paragraph(true),
+            0..=3 => TempBlock::check_block_known_indent(line, options)
+                .into_line_result_paragraph(true),
             4.. => {
                 self.push(line);
                 LineResult::None
@@ -1,8 +1,9 @@
 use std::iter;
 
-use crate::ast::{Block, new_list_attributes};
+use crate::ast::{Block, Inline, new_list_attributes};
+use crate::md_reader::inline_parser::InlineParser;
 use crate::md_reader::iters::SkipIndent;
-use crate::md_reader::Links;
+use crate::md_reader::{Links, MdReaderOptions, Warning};
 use crate::md_reader::temp_block::{
     CheckResult, IndentedCodeBlock, LineResult, SkipIndentResult, TempBlock, ThematicBreak,
 };
@@ -25,6 +26,8 @@ pub struct List {
 enum ListType {
     Unordered(char),
     Ordered(Ordered),
+    /// Definitions of a single term, the term is kept unparsed
+    Definition(String),
 }
 
 #[derive(Debug)]
@@ -41,54 +44,68 @@ impl List {
 
     /// Checks if the line is the beginning of a list assuming the first char is a `'*'` or a `'-'`
     /// and the line doesn't come after a paragraph
-    pub fn check_star_dash(line: SkipIndent) -> CheckResult {
+    pub fn check_star_dash(line: SkipIndent, options: MdReaderOptions) -> CheckResult {
         let c = line.first;
-        Item::check_star_dash(line).into_check_result(c)
+        Item::check_star_dash(line, options).into_check_result(c)
     }
 
     /// Checks if the line is the beginning of a list assuming the first char is a `'*'` and the
     /// line comes after a paragraph
-    pub fn check_star_paragraph(line: SkipIndent) -> CheckResult {
-        Item::check_star_paragraph(line).into_check_result('*')
+    pub fn check_star_paragraph(line: SkipIndent, options: MdReaderOptions) -> CheckResult {
+        Item::check_star_paragraph(line, options).into_check_result('*')
     }
 
     /// Checks if the line is the beginning of a list assuming the first char is a `'-'` and the
     /// line comes after a paragraph
-    pub fn check_dash_paragraph(line: SkipIndent) -> CheckOrSetextResult {
-        Item::check_dash_paragraph(line)
+    pub fn check_dash_paragraph(line: SkipIndent, options: MdReaderOptions) -> CheckOrSetextResult {
+        Item::check_dash_paragraph(line, options)
     }
 
     /// Checks if the line is the beginning of a list assuming the first char is a `'+'` and the
     /// line doesn't come after a paragraph
-    pub fn check_plus(line: SkipIndent) -> CheckResult {
-        Item::check_plus(line).into_check_result('+')
+    pub fn check_plus(line: SkipIndent, options: MdReaderOptions) -> CheckResult {
+        Item::check_plus(line, options).into_check_result('+')
     }
 
     /// Checks if the line is the beginning of a list assuming the first char is a `'+'` and the
     /// line comes after a paragraph
-    pub fn check_plus_paragraph(line: SkipIndent) -> CheckResult {
-        Item::check_plus_paragraph(line).into_check_result('+')
+    pub fn check_plus_paragraph(line: SkipIndent, options: MdReaderOptions) -> CheckResult {
+        Item::check_plus_paragraph(line, options).into_check_result('+')
     }
 
     /// Checks if the line is the beginning of a list assuming the first char is a digit from and
     /// the line doesn't come after a paragraph
-    pub fn check_number(line: SkipIndent) -> CheckResult {
-        Item::check_number(line).into_check_result()
+    pub fn check_number(line: SkipIndent, options: MdReaderOptions) -> CheckResult {
+        Item::check_number(line, options).into_check_result()
     }
 
     /// Checks if the line is the beginning of a list assuming the first char is `'1'`  and the line
     /// comes after a paragraph
-    pub fn check_number_paragraph(line: SkipIndent) -> CheckResult {
-        Item::check_number_paragraph(line)
+    pub fn check_number_paragraph(line: SkipIndent, options: MdReaderOptions) -> CheckResult {
+        Item::check_number_paragraph(line, options)
+    }
+
+    /// Checks if the line is the beginning of a definition of a given term assuming the first char
+    /// is a `':'` and the line comes after a paragraph made of the term
+    pub fn check_definition_paragraph<'a>(
+        line: SkipIndent<'a>, term: &str, options: MdReaderOptions,
+    ) -> CheckResult<'a> {
+        match Item::check_plus_paragraph(line, options) {
+            NewItemResult::New(i) =>
+                CheckResult::New(Self::new(i, ListType::Definition(term.to_owned())).into()),
+            NewItemResult::Text(s) => CheckResult::Text(s),
+        }
     }
 
     /// Parses a non-blank line of a document
-    pub fn next(&mut self, mut line: SkipIndent, links: &mut Links) -> LineResult {
+    pub fn next(
+        &mut self, mut line: SkipIndent, links: &mut Links, options: MdReaderOptions,
+    ) -> LineResult {
         if let Some(current) = self.current.as_mut() 
         {
             if line.indent >= current.indent + current.width {
                 line.move_indent(current.indent + current.width);
-                current.next_line(line, links);
+                current.next_line(line, links, options);
                 return LineResult::None
             }
         }
@@ -100,24 +117,26 @@ impl List {
         } else {
             // Check for list items, if matching the type
             let line = match &self.list_type {
-                ListType::Unordered('+') if line.first == '+' => match Item::check_plus(line) {
-                    NewItemResult::New(i) => {
-                        self.add_item(i, links);
-                        return LineResult::None;
+                ListType::Unordered('+') if line.first == '+' =>
+                    match Item::check_plus(line, options) {
+                        NewItemResult::New(i) => {
+                            self.add_item(i, links);
+                            return LineResult::None;
+                        },
+                        NewItemResult::Text(s) => s,
                     },
-                    NewItemResult::Text(s) => s,
-                },
-                ListType::Unordered(c) if line.first == *c => match Item::check_star_dash(line) {
-                    NewItemBreakResult::New(i) => {
-                        self.add_item(i, links);
-                        return LineResult::None;
+                ListType::Unordered(c) if line.first == *c =>
+                    match Item::check_star_dash(line, options) {
+                        NewItemBreakResult::New(i) => {
+                            self.add_item(i, links);
+                            return LineResult::None;
+                        },
+                        NewItemBreakResult::Break =>
+                            return LineResult::DoneSelfAndOther(ThematicBreak.into()),
+                        NewItemBreakResult::Text(s) => s,
                     },
-                    NewItemBreakResult::Break =>
-                        return LineResult::DoneSelfAndOther(ThematicBreak.into()),
-                    NewItemBreakResult::Text(s) => s,
-                },
                 ListType::Ordered(Ordered { closing, .. }) if line.first.is_ascii_digit() =>
-                    match Item::check_number(line) {
+                    match Item::check_number(line, options) {
                         NewOrderedItemResult::New(i, o) =>
                             return if o.closing == *closing {
                                 self.add_item(i, links);
@@ -129,11 +148,20 @@ impl List {
                             },
                         NewOrderedItemResult::Text(s) => s,
                     },
+                ListType::Definition(_) if line.first == ':' =>
+                    match Item::check_plus(line, options) {
+                        NewItemResult::New(i) => {
+                            self.add_item(i, links);
+                            return LineResult::None;
+                        },
+                        NewItemResult::Text(s) => s,
+                    },
                 _ => line,
             };
             match self.current.as_mut() {
-                Some(s) => s.current.next_continuation(line),
-                None => TempBlock::check_block_known_indent(line).into_line_result_paragraph(true),
+                Some(s) => s.current.next_continuation(line, options),
+                None => TempBlock::check_block_known_indent(line, options)
+                    .into_line_result_paragraph(true),
             }
         }
     }
@@ -146,18 +174,24 @@ impl List {
     }
 
     /// Finishes the list into a [`Block`]
-    pub fn finish(mut self, links: &Links) -> Block {
+    pub fn finish(
+        mut self, links: &Links, options: MdReaderOptions, warnings: &mut Vec<Warning>,
+    ) -> Block {
         self.check_end();
         let done = self
             .items
             .into_iter()
             .chain(self.current)
-            .map(|i| i.finish(self.loose, links))
+            .map(|i| i.finish(self.loose, links, options, warnings))
             .collect();
         match self.list_type {
             ListType::Unordered(_) => Block::BulletList(done),
             ListType::Ordered(Ordered { starting, closing }) =>
                 Block::OrderedList(new_list_attributes(starting, closing), done),
+            ListType::Definition(term) => {
+                let term = InlineParser::parse_lines(&term, links, options);
+                Block::DefinitionList(vec![(term, done)])
+            },
         }
     }
 
@@ -283,8 +317,8 @@ impl Item {
     }
 
     /// Creates a new item parsing the first line into a block
-    fn new(width: usize, indent: usize, content: SkipIndent) -> Self {
-        let (current, finished) = TempBlock::new_empty_known_indent(content);
+    fn new(width: usize, indent: usize, content: SkipIndent, options: MdReaderOptions) -> Self {
+        let (current, finished) = TempBlock::new_empty_known_indent(content, options);
         Self { finished, current: Box::new(current), width, indent, gap: false, loose: false }
     }
 
@@ -303,18 +337,18 @@ impl Item {
 
     /// Checks if a line begins a list item assuming it starts with a `'*'` or a `'-'` and the line
     /// doesn't come after a paragraph
-    fn check_star_dash(line: SkipIndent) -> NewItemBreakResult {
+    fn check_star_dash(line: SkipIndent, options: MdReaderOptions) -> NewItemBreakResult {
         match line.skip_indent_rest() {
-            SkipIndentResult::Line(rest) => Self::check_star_dash_known(line, rest),
+            SkipIndentResult::Line(rest) => Self::check_star_dash_known(line, rest, options),
             SkipIndentResult::Blank(_) => NewItemBreakResult::New(Self::new_empty(2, line.indent)),
         }
     }
 
     /// Checks if a line begins a list item assuming it starts with a `'*'` and the line comes after
     /// a paragraph
-    fn check_star_paragraph(line: SkipIndent) -> NewItemBreakResult {
+    fn check_star_paragraph(line: SkipIndent, options: MdReaderOptions) -> NewItemBreakResult {
         match line.skip_indent_rest() {
-            SkipIndentResult::Line(rest) => Self::check_star_dash_known(line, rest),
+            SkipIndentResult::Line(rest) => Self::check_star_dash_known(line, rest, options),
             SkipIndentResult::Blank(_) => NewItemBreakResult::Text(line),
         }
     }
@@ -323,18 +357,18 @@ impl Item {
     /// assuming the line either starts with a `'*'` or it starts with a `'-'` and comes after the
     /// paragraph
     fn check_star_dash_known<'a>(
-        line: SkipIndent<'a>, rest: SkipIndent<'a>,
+        line: SkipIndent<'a>, rest: SkipIndent<'a>, options: MdReaderOptions,
     ) -> NewItemBreakResult<'a> {
         if Self::check_thematic(&line, &rest) {
             NewItemBreakResult::Break
         } else {
-            Self::check_unordered_known(line, rest).into()
+            Self::check_unordered_known(line, rest, options).into()
         }
     }
 
     /// Checks if a line begins a list item assuming it starts with a `'-'` and the line comes after
     /// a paragraph
-    fn check_dash_paragraph(line: SkipIndent) -> CheckOrSetextResult {
+    fn check_dash_paragraph(line: SkipIndent, options: MdReaderOptions) -> CheckOrSetextResult {
         match line.skip_indent_rest() {
             SkipIndentResult::Line(rest) =>
                 if rest.indent == 0 {
@@ -343,7 +377,7 @@ impl Item {
                     CheckOrSetextResult::Check(CheckResult::Done(ThematicBreak.into()))
                 } else {
                     let item = if rest.indent < 5 {
-                        Self::new(1 + rest.indent, line.indent, rest)
+                        Self::new(1 + rest.indent, line.indent, rest, options)
                     } else {
                         Self::new_code(2, line.indent, rest)
                     };
@@ -355,37 +389,39 @@ impl Item {
         }
     }
 
-    /// Checks if a line begins a list item assuming it starts with a `'+'` and the line doesn't
-    /// come after a paragraph
-    fn check_plus(line: SkipIndent) -> NewItemResult {
+    /// Checks if a line begins a list item assuming it starts with a `'+'` (or a `':'` in a
+    /// definition list) and the line doesn't come after a paragraph
+    fn check_plus(line: SkipIndent, options: MdReaderOptions) -> NewItemResult {
         match line.skip_indent_rest() {
-            SkipIndentResult::Line(rest) => Self::check_unordered_known(line, rest),
+            SkipIndentResult::Line(rest) => Self::check_unordered_known(line, rest, options),
             SkipIndentResult::Blank(_) => NewItemResult::New(Self::new_empty(2, line.indent)),
         }
     }
 
-    /// Checks if a line begins a list item assuming it starts with a `'+'` and the line comes after
-    /// a paragraph
-    fn check_plus_paragraph(line: SkipIndent) -> NewItemResult {
+    /// Checks if a line begins a list item assuming it starts with a `'+'` (or a `':'` in a
+    /// definition list) and the line comes after a paragraph
+    fn check_plus_paragraph(line: SkipIndent, options: MdReaderOptions) -> NewItemResult {
         match line.skip_indent_rest() {
-            SkipIndentResult::Line(rest) => Self::check_unordered_known(line, rest),
+            SkipIndentResult::Line(rest) => Self::check_unordered_known(line, rest, options),
             SkipIndentResult::Blank(_) => NewItemResult::Text(line),
         }
     }
 
     /// Checks if a line begins a list item knowing the rest of the line is not empty and assuming
     /// all other necessary checks that would prevent a list item from beginning were passed
-    fn check_unordered_known<'a>(line: SkipIndent<'a>, rest: SkipIndent<'a>) -> NewItemResult<'a> {
+    fn check_unordered_known<'a>(
+        line: SkipIndent<'a>, rest: SkipIndent<'a>, options: MdReaderOptions,
+    ) -> NewItemResult<'a> {
         match rest.indent {
             0 => NewItemResult::Text(line),
-            i @ 1..=4 => NewItemResult::New(Self::new(1 + i, line.indent, rest)),
+            i @ 1..=4 => NewItemResult::New(Self::new(1 + i, line.indent, rest, options)),
             5.. => NewItemResult::New(Self::new_code(2, line.indent, rest)),
         }
     }
 
     /// Checks if a line begins a list item assuming it starts with a digit and the line doesn't
     /// come after a paragraph
-    fn check_number(line: SkipIndent) -> NewOrderedItemResult {
+    fn check_number(line: SkipIndent, options: MdReaderOptions) -> NewOrderedItemResult {
         let mut iter = line.indent_iter_rest();
         let (Some((starting, width)), Some(closing)) =
             (iter.get_number(line.first), iter.get_closing())
@@ -396,7 +432,7 @@ impl Item {
             SkipIndentResult::Line(rest) => match rest.indent {
                 0 => NewOrderedItemResult::Text(line),
                 i @ 1..=4 => NewOrderedItemResult::New(
-                    Self::new(width + 1 + i, line.indent, rest),
+                    Self::new(width + 1 + i, line.indent, rest, options),
                     Ordered { starting, closing },
                 ),
                 5.. => NewOrderedItemResult::New(
@@ -414,7 +450,7 @@ impl Item {
 
     /// Checks if a line begins a list item assuming it starts with a `'1'` and the line comes after
     /// a paragraph
-    fn check_number_paragraph(line: SkipIndent) -> CheckResult {
+    fn check_number_paragraph(line: SkipIndent, options: MdReaderOptions) -> CheckResult {
         let mut iter = line.indent_iter_rest();
         let Some(closing) = iter.get_closing() else {
             return CheckResult::Text(line);
@@ -424,7 +460,7 @@ impl Item {
             SkipIndentResult::Line(rest) => match rest.indent {
                 0 => CheckResult::Text(line),
                 i @ 1..=4 => CheckResult::New(
-                    List::new(Self::new(2 + i, line.indent, rest), list_type).into(),
+                    List::new(Self::new(2 + i, line.indent, rest, options), list_type).into(),
                 ),
                 5.. => CheckResult::New(
                     List::new(Self::new_code(3, line.indent, rest), list_type).into(),
@@ -480,8 +516,8 @@ impl Item {
     }
 
     /// Parses a non-blank line of the document
-    fn next_line(&mut self, line: SkipIndent, links: &mut Links) {
-        let result = self.current.next_line(line, links);
+    fn next_line(&mut self, line: SkipIndent, links: &mut Links, options: MdReaderOptions) {
+        let result = self.current.next_line(line, links, options);
         if !self.loose
             && (result.is_done_or_new() && self.gap
                 || result.is_done_self_and_new_or_other() && self.current.ends_with_gap())
@@ -506,13 +542,22 @@ impl Item {
     }
 
     /// Finishes this item into a [`Vec`] of [`Block`] elements
-    fn finish(self, loose: bool, links: &Links) -> Vec<Block> {
-        let temp = self
-            .finished
-            .into_iter()
-            .chain(iter::once(*self.current))
-            .filter_map(|t| t.finish(links));
-        if loose {
+    fn finish(
+        mut self, loose: bool, links: &Links, options: MdReaderOptions,
+        warnings: &mut Vec<Warning>,
+    ) -> Vec<Block> {
+        let task = match self.finished.first_mut().unwrap_or(&mut self.current) {
+            TempBlock::Paragraph(p) if options.tasklists => p.take_task_marker(),
+            _ => None,
+        };
+        let temp = TempBlock::merge_definitions(
+            self.finished
+                .into_iter()
+                .chain(iter::once(*self.current))
+                .filter_map(|t| t.finish(links, options, warnings)),
+        )
+        .into_iter();
+        let mut result: Vec<_> = if loose {
             temp.collect()
         } else {
             temp.map(|b| match b {
@@ -520,7 +565,13 @@ impl Item {
                 b => b,
             })
             .collect()
+        };
+        if let (Some(checked), Some(Block::Para(i) | Block::Plain(i))) = (task, result.first_mut())
+        {
+            let marker = if checked { "☒" } else { "☐" };
+            i.splice(..0, [Inline::Str(String::from(marker)), Inline::Space]);
         }
+        result
     }
 }
 
@@ -530,7 +581,8 @@ mod tests {
 
     fn new_dash(line: &str) -> List {
         #[allow(clippy::single_match_else)]
-        match List::check_star_dash(SkipIndent::skip(line, 0).into_line()) {
+        let line = SkipIndent::skip(line, 0).into_line();
+        match List::check_star_dash(line, MdReaderOptions::default()) {
             CheckResult::New(TempBlock::List(l)) => l,
             _ => panic!(),
         }
@@ -538,7 +590,8 @@ mod tests {
 
     fn new_plus(line: &str) -> List {
         #[allow(clippy::single_match_else)]
-        match List::check_plus(SkipIndent::skip(line, 0).into_line()) {
+        let line = SkipIndent::skip(line, 0).into_line();
+        match List::check_plus(line, MdReaderOptions::default()) {
             CheckResult::New(TempBlock::List(l)) => l,
             _ => panic!(),
         }
@@ -546,14 +599,19 @@ mod tests {
 
     fn new_number(line: &str) -> List {
         #[allow(clippy::single_match_else)]
-        match List::check_number(SkipIndent::skip(line, 0).into_line()) {
+        let line = SkipIndent::skip(line, 0).into_line();
+        match List::check_number(line, MdReaderOptions::default()) {
             CheckResult::New(TempBlock::List(l)) => l,
             _ => panic!(),
         }
     }
 
     fn next(list: &mut List, line: &str) -> LineResult {
-        list.next(SkipIndent::skip(line, 0).into_line(), &mut Links::new())
+        list.next(
+            SkipIndent::skip(line, 0).into_line(),
+            &mut Links::new(),
+            MdReaderOptions::default(),
+        )
     }
 
     fn next_blank(list: &mut List) { list.next_blank(0, &mut Links::new()); }
@@ -613,6 +671,66 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn wide_ordered_marker() {
+        let mut list = new_number("10. text");
+        assert!(matches!(next(&mut list, "    more"), LineResult::None));
+        assert!(list.current.as_ref().is_some_and(
+            |i| i.finished.is_empty() && matches!(i.current.as_ref(), TempBlock::Paragraph(_))
+        ));
+        let mut list = new_number("10. text");
+        next_blank(&mut list);
+        assert!(matches!(next(&mut list, "    more"), LineResult::None));
+        assert!(list.items.is_empty());
+        assert!(list.current.as_ref().is_some_and(|i| i.width == 4 && i.finished.len() == 1));
+        let mut list = new_number("10. text");
+        next_blank(&mut list);
+        assert!(matches!(
+            next(&mut list, "   more"),
+            LineResult::DoneSelfAndNew(TempBlock::Paragraph(_))
+        ));
+        let mut list = new_number("100)  text");
+        next_blank(&mut list);
+        assert!(matches!(next(&mut list, "      more"), LineResult::None));
+        assert!(list.current.is_some_and(|i| i.width == 6 && i.finished.len() == 1));
+    }
+
+    #[test]
+    fn code_after_marker() {
+        let is_code = |l: List| {
+            l.current.is_some_and(|i| matches!(i.current.as_ref(), TempBlock::IndentedCodeBlock(_)))
+        };
+        let width = |l: List| l.current.map(|i| i.width);
+        assert!(is_code(new_dash("-     code")));
+        assert_eq!(width(new_dash("-     code")), Some(2));
+        assert!(!is_code(new_dash("-    text")));
+        assert_eq!(width(new_dash("-    text")), Some(5));
+        assert!(is_code(new_plus("+     code")));
+        assert!(is_code(new_number("1.     code")));
+        assert_eq!(width(new_number("1.     code")), Some(3));
+        assert!(!is_code(new_number("1.    text")));
+        assert_eq!(width(new_number("1.    text")), Some(6));
+        let mut list = new_dash("-     code");
+        assert!(matches!(next(&mut list, "      more"), LineResult::None));
+        assert!(is_code(list));
+    }
+
+    #[test]
+    fn empty_item_blank_line() {
+        let mut list = new_dash("-");
+        next_blank(&mut list);
+        assert!(list.current.is_none());
+        assert_eq!(list.items.len(), 1);
+        assert!(matches!(
+            next(&mut list, "  text"),
+            LineResult::DoneSelfAndNew(TempBlock::Paragraph(_))
+        ));
+        let mut list = new_dash("-");
+        assert!(matches!(next(&mut list, "  text"), LineResult::None));
+        next_blank(&mut list);
+        assert!(list.current.is_some());
+    }
+
     #[test]
     fn matching_list_item() {
         let mut list = new_plus("+ list");
@@ -702,13 +820,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn trailing_blank_lines() {
+        assert!(!new_dash_all(["- a", "- b", ""]).loose);
+        assert!(!new_dash_all(["- a", "- b", "", ""]).loose);
+        assert!(!new_dash_all(["- a", "  - b", "", ""]).loose);
+        assert!(!new_dash_all(["- a", "-", ""]).loose);
+        assert!(!new_dash_all(["- a", "-     code", ""]).loose);
+        assert!(new_dash_all(["- a", "- b", "", "  c", ""]).loose);
+        assert!(new_dash_all(["- a", "", "  b", "- c", ""]).loose);
+    }
+
+    #[test]
+    fn tight_nested_paragraphs() {
+        let list = new_dash_all(["- a", "  > b", "  > ", "  > c", "  - d"]);
+        let block = list.finish(&Links::new(), MdReaderOptions::default(), &mut Vec::new());
+        let Block::BulletList(items) = block else { panic!() };
+        let [item] = items.as_slice() else { panic!() };
+        let [Block::Plain(_), Block::BlockQuote(quote), Block::BulletList(nested)] = item.as_slice()
+        else {
+            panic!()
+        };
+        assert!(matches!(quote.as_slice(), [Block::Para(_), Block::Para(_)]));
+        assert!(matches!(nested.as_slice(), [n] if matches!(n.as_slice(), [Block::Plain(_)])));
+    }
+
     fn check<'a, F, M, T>(check: F, matches: M, line: &'a str)
     where
-        F: FnOnce(SkipIndent<'a>) -> T,
+        F: FnOnce(SkipIndent<'a>, MdReaderOptions) -> T,
         M: FnOnce(T) -> bool,
         T: 'a,
     {
-        assert!(matches(check(SkipIndent::skip(line, 0).into_line())));
+        assert!(matches(check(SkipIndent::skip(line, 0).into_line(), MdReaderOptions::default())));
     }
 
     #[test]
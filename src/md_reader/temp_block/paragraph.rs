@@ -1,9 +1,10 @@
 use crate::ast::{Block, Inline};
 use crate::md_reader::inline_parser::InlineParser;
 use crate::md_reader::iters::{Iter, SkipIndent};
+use crate::md_reader::{HtmlMode, MdReaderOptions};
 use crate::md_reader::temp_block::{
-    AtxHeading, BlockQuote, CheckOrSetextResult, CheckResult, FencedCodeBlock, LineResult, Links,
-    List, NewResult, Table, TempBlock, ThematicBreak,
+    AtxHeading, BlockQuote, CheckOrSetextResult, CheckResult, FencedCodeBlock, HtmlBlock,
+    HtmlComment, LineResult, Links, List, NewResult, Table, TempBlock, ThematicBreak,
 };
 
 /// Struct representing an unfinished paragraph
@@ -19,31 +20,40 @@ pub struct Paragraph {
     setext: usize,
     /// Number of `'='` or `'-'` characters used to make this paragraph a setext header
     setext_char_count: usize,
+    /// Whether the last line ended with at least two spaces, which makes a hard line break if
+    /// another line follows
+    hard_break: bool,
 }
 
 impl Paragraph {
     /// Creates a new paragraph starting with a given non-blank line
     pub fn new(line: &SkipIndent) -> Self {
         Self {
-            content: line.line.to_owned(),
+            content: line.line.trim_end().to_owned(),
             line_start: 0,
             table_header_length: Table::check_header(line.line),
             setext: 0,
             setext_char_count: 0,
+            hard_break: line.line.ends_with("  "),
         }
     }
 
     /// Parses a non-blank line of a document
-    pub fn next(&mut self, line: SkipIndent) -> LineResult {
+    pub fn next(&mut self, line: SkipIndent, options: MdReaderOptions) -> LineResult {
         let checked = match line.indent {
             0..=3 => match line.first {
-                '=' => return self.push_check_setext(line),
+                '=' => return self.push_check_setext(line, options),
                 '#' => AtxHeading::check(line),
                 '_' => ThematicBreak::check(line),
                 '~' | '`' => FencedCodeBlock::check(line),
-                '>' => CheckResult::New(BlockQuote::new(&line).into()),
-                '*' => List::check_star_paragraph(line),
-                '-' => match List::check_dash_paragraph(line) {
+                '<' => match HtmlComment::check(line) {
+                    CheckResult::Text(line) if options.html != HtmlMode::Escape =>
+                        HtmlBlock::check(line, true),
+                    c => c,
+                },
+                '>' => CheckResult::New(BlockQuote::new(&line, options).into()),
+                '*' => List::check_star_paragraph(line, options),
+                '-' => match List::check_dash_paragraph(line, options) {
                     CheckOrSetextResult::Check(c) => c,
                     CheckOrSetextResult::Setext(n) => {
                         self.setext = 2;
@@ -51,8 +61,13 @@ impl Paragraph {
                         return LineResult::DoneSelf;
                     },
                 },
-                '+' => List::check_plus_paragraph(line),
-                '1' => List::check_number_paragraph(line),
+                '+' => List::check_plus_paragraph(line, options),
+                '1' => List::check_number_paragraph(line, options),
+                ':' if options.definition_lists && !self.content.contains('\n') =>
+                    match List::check_definition_paragraph(line, &self.content, options) {
+                        CheckResult::New(b) => return LineResult::New(b),
+                        c => c,
+                    },
                 _ => CheckResult::Text(line),
             },
             4.. => {
@@ -60,12 +75,12 @@ impl Paragraph {
                 return LineResult::None;
             },
         };
-        checked.into_line_result(true, |s| self.push_full_check(s))
+        checked.into_line_result(true, |s| self.push_full_check(s, options))
     }
 
     /// Parses a non-blank line of a document as a continuation line indented at most 3 spaces
-    pub fn next_continuation(&mut self, line: SkipIndent) -> LineResult {
-        TempBlock::check_block_known_indent(line).into_line_result(true, |s| {
+    pub fn next_continuation(&mut self, line: SkipIndent, options: MdReaderOptions) -> LineResult {
+        TempBlock::check_block_known_indent(line, options).into_line_result(true, |s| {
             self.push_header_no_indent_check(&s);
             LineResult::None
         })
@@ -74,6 +89,24 @@ impl Paragraph {
     /// Parses a non-blank line of a document as a continuation line indented at least 4 spaces
     pub fn next_indented_continuation(&mut self, line: &SkipIndent) { self.push(line.line); }
 
+    /// Removes a task list marker `[ ]`, `[x]` or `[X]` followed by whitespace from the start of
+    /// the paragraph, returning whether the task is checked if there was one
+    pub fn take_task_marker(&mut self) -> Option<bool> {
+        let checked = match self.content.get(..3)? {
+            "[ ]" => false,
+            "[x]" | "[X]" => true,
+            _ => return None,
+        };
+        let rest = self.content[3..].trim_start_matches([' ', '\t']);
+        let removed = self.content.len() - rest.len();
+        if removed == 3 || rest.is_empty() || rest.starts_with('\n') {
+            return None;
+        }
+        self.content.drain(..removed);
+        self.line_start = self.line_start.saturating_sub(removed);
+        Some(checked)
+    }
+
     /// Extracts links from this paragraph adding them into the `links` argument
     pub fn add_links(&mut self, links: &mut Links) {
         let mut iter = Iter::new(&self.content);
@@ -137,18 +170,22 @@ impl Paragraph {
 
     /// Gets last line of this paragraph
     pub fn get_last_line(&self) -> &str {
+        debug_assert!(self.content.is_char_boundary(self.line_start));
         // Safety: self.line_start is 0 at first (beginning of the string) and before each push it's
         // set to the end of the previous string, therefore it's always on a UTF-8 char boundary
         unsafe { self.content.get_unchecked(self.line_start..) }
     }
 
     /// Trims last line and the preceding new line character
-    pub fn trim_last_line(&mut self) { self.content.truncate(self.line_start.saturating_sub(1)); }
+    pub fn trim_last_line(&mut self) {
+        self.content.truncate(self.line_start.saturating_sub(1));
+        self.content.truncate(self.content.trim_end().len());
+    }
 
     /// Finishes the paragraph into a [`Block`]. If the content is empty and the block would be a
     /// setext heading it becomes a paragraph with just the setext heading underline. An empty
     /// paragraph returns [`None`].
-    pub fn finish(self, links: &Links) -> Option<Block> {
+    pub fn finish(self, links: &Links, options: MdReaderOptions) -> Option<Block> {
         if self.content.is_empty() {
             let char = match self.setext {
                 0 => return None,
@@ -158,7 +195,7 @@ impl Paragraph {
             };
             Some(Block::Para(vec![Inline::Str(char.repeat(self.setext_char_count))]))
         } else {
-            let parsed = InlineParser::parse_lines(&self.content, links);
+            let parsed = InlineParser::parse_lines(&self.content, links, options);
             Some(match self.setext {
                 0 => Block::Para(parsed),
                 _ => Block::new_header(self.setext, parsed),
@@ -169,7 +206,7 @@ impl Paragraph {
     /// Pushes a line checking if it's a setext heading underline first, if it's not it performs a
     /// full [`Table`] check - check if a table is created and if not check whether the new
     /// line can be a table header.
-    fn push_check_setext(&mut self, line: SkipIndent) -> LineResult {
+    fn push_check_setext(&mut self, line: SkipIndent, options: MdReaderOptions) -> LineResult {
         let mut iter = line.iter_rest();
         iter.skip_while_eq('=');
         iter.skip_whitespace();
@@ -177,13 +214,18 @@ impl Paragraph {
             self.setext = 1;
             LineResult::DoneSelf
         } else {
-            self.push_full_check(line)
+            self.push_full_check(line, options)
         }
     }
 
     /// Pushes a line performing a full [`Table`] check first - check if a table is created and if
-    /// not check whether the new line can be a table header.
-    fn push_full_check(&mut self, line: SkipIndent) -> LineResult {
+    /// not check whether the new line can be a table header. Tables are not created without
+    /// [`MdReaderOptions::tables`]
+    fn push_full_check(&mut self, line: SkipIndent, options: MdReaderOptions) -> LineResult {
+        if !options.tables {
+            self.push(line.line);
+            return LineResult::None;
+        }
         match Table::check(line, self) {
             NewResult::New(b) =>
                 if self.line_start == 0 {
@@ -216,9 +258,13 @@ impl Paragraph {
 
     /// Pushes a line without any checks
     fn push(&mut self, line: &str) {
+        if self.hard_break {
+            self.content.push_str("  ");
+        }
         self.content.push('\n');
         self.line_start = self.content.len();
         self.content.push_str(line.trim_end());
+        self.hard_break = line.ends_with("  ");
     }
 }
 
@@ -232,7 +278,7 @@ mod tests {
         let mut paragraph = Paragraph::new(&SkipIndent::skip(iter.next().unwrap(), 0).into_line());
         for s in iter {
             assert!(matches!(
-                paragraph.next(SkipIndent::skip(s, 0).into_line()),
+                paragraph.next(SkipIndent::skip(s, 0).into_line(), MdReaderOptions::default()),
                 LineResult::None | LineResult::DoneSelf
             ));
         }
@@ -244,7 +290,7 @@ mod tests {
     fn assert_links<'a, I>(i: I, paragraph: bool, links: usize)
     where I: IntoIterator<Item = &'a str> {
         let (p, l) = get_links(i);
-        assert_eq!(p.finish(&l).is_some(), paragraph);
+        assert_eq!(p.finish(&l, MdReaderOptions::default()).is_some(), paragraph);
         assert_eq!(l.len(), links);
     }
 
@@ -287,4 +333,16 @@ mod tests {
         assert_links(["[foo]: url 'title'", "======"], true, 1);
         assert_links(["[foo]: url 'title'", "------"], true, 1);
     }
+
+    #[test]
+    fn multi_line_titles() {
+        let title = |lines: &[&str]| {
+            let (_, links) = get_links(lines.iter().copied());
+            links.get("foo").unwrap().title.clone().unwrap()
+        };
+        assert_eq!(title(&["[foo]: url 'title", "continued'"]), "title\ncontinued");
+        assert_eq!(title(&["[foo]: url \"title", "continued\""]), "title\ncontinued");
+        assert_eq!(title(&["[foo]: url (title", "continued)"]), "title\ncontinued");
+        assert_eq!(title(&["[foo]:", "url", "'title", "continued'"]), "title\ncontinued");
+    }
 }
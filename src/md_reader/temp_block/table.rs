@@ -1,6 +1,8 @@
+use std::mem;
+
 use crate::ast::{Alignment, Block};
 use crate::md_reader::iters::SkipIndent;
-use crate::md_reader::Links;
+use crate::md_reader::{Links, MdReaderOptions, Warning};
 use crate::md_reader::temp_block::{LineResult, NewResult, Paragraph, TempBlock};
 
 /// Struct representing an unfinished table
@@ -10,6 +12,8 @@ pub struct Table {
     alignments: Vec<Alignment>,
     /// Table rows
     rows: Vec<Vec<String>>,
+    /// Rows with a cell count different from the header, with the found count
+    ragged: Vec<(String, usize)>,
 }
 
 impl Table {
@@ -43,7 +47,7 @@ impl Table {
         }
         iter.skip_whitespace();
         if iter.ended() {
-            let mut result = Self { alignments, rows: Vec::new() };
+            let mut result = Self { alignments, rows: Vec::new(), ragged: Vec::new() };
             result.push(paragraph.get_last_line());
             paragraph.trim_last_line();
             NewResult::New(result.into())
@@ -53,16 +57,27 @@ impl Table {
     }
 
     /// Parses next non-blank line of a document
-    pub fn next(&mut self, line: SkipIndent) -> LineResult {
-        TempBlock::check_block(line).into_line_result(true, |s| {
+    pub fn next(&mut self, line: SkipIndent, options: MdReaderOptions) -> LineResult {
+        TempBlock::check_block(line, options).into_line_result(true, |s| {
             self.push(s.line);
             LineResult::None
         })
     }
 
-    /// Finishes the table into a [`Block`]
-    pub fn finish(self, links: &Links) -> Block {
-        Block::new_table(self.rows, self.alignments, links)
+    /// Finishes the table into a [`Block`]. In [`MdReaderOptions::strict_tables`] mode pushes a
+    /// warning for every row with a cell count different from the header
+    pub fn finish(
+        self, links: &Links, options: MdReaderOptions, warnings: &mut Vec<Warning>,
+    ) -> Block {
+        if options.strict_tables {
+            let expected = self.alignments.len();
+            warnings.extend(
+                self.ragged
+                    .into_iter()
+                    .map(|(row, found)| Warning::RaggedTableRow { row, expected, found }),
+            );
+        }
+        Block::new_table(self.rows, self.alignments, links, options)
     }
 
     /// Checks how many columns a table header defined by this line has
@@ -92,26 +107,30 @@ impl Table {
         count
     }
 
-    /// Pushes a line splitting it into cells
+    /// Pushes a line splitting it into cells. Excess cells are ignored and missing cells are
+    /// added as empty
     fn push(&mut self, line: &str) {
         let mut iter = line.trim().chars().peekable();
         iter.next_if_eq(&'|');
         let mut result = Vec::new();
         let mut current = String::new();
-        loop {
-            match iter.next() {
-                Some('\\') => current.push(iter.next_if_eq(&'|').unwrap_or('\\')),
-                Some('|') | None => {
-                    result.push(current);
-                    if result.len() == self.alignments.len() {
-                        self.rows.push(result);
-                        return;
-                    }
-                    current = String::new();
-                },
-                Some(c) => current.push(c),
-            };
+        let mut closed = false;
+        while let Some(c) = iter.next() {
+            closed = c == '|';
+            match c {
+                '\\' => current.push(iter.next_if_eq(&'|').unwrap_or('\\')),
+                '|' => result.push(mem::take(&mut current)),
+                c => current.push(c),
+            }
+        }
+        if !closed {
+            result.push(current);
+        }
+        if result.len() != self.alignments.len() {
+            self.ragged.push((line.trim().to_owned(), result.len()));
         }
+        result.resize(self.alignments.len(), String::new());
+        self.rows.push(result);
     }
 }
 
@@ -159,7 +178,8 @@ mod tests {
     }
 
     fn push(line: &str, size: usize, expected: &[&str]) {
-        let mut table = Table { alignments: vec![Alignment::Center; size], rows: Vec::new() };
+        let alignments = vec![Alignment::Center; size];
+        let mut table = Table { alignments, rows: Vec::new(), ragged: Vec::new() };
         table.push(line);
         let result: Vec<_> = table.rows.last().unwrap().iter().map(String::as_str).collect();
         assert_eq!(result, expected);
@@ -0,0 +1,176 @@
+//! Module containing the [`TableStyle`] type used for laying out tables in GitHub Flavoured
+//! Markdown. It is a part of the planned Markdown writer
+
+use crate::ast::{Alignment, ColSpec};
+
+/// Style of tables written to Markdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableStyle {
+    /// GitHub Flavoured Markdown pipe tables. Tables with cells that don't fit in a single line
+    /// are written as [`TableStyle::Grid`] instead
+    #[default]
+    Pipe,
+    /// Pandoc grid tables supporting cells with multiple lines
+    Grid,
+}
+
+impl TableStyle {
+    /// Lays out a table with cells already written to Markdown. Every row should have a cell for
+    /// each column of `spec` and `|` characters in cells should be escaped. Cells containing line
+    /// breaks and tables with more than one header row can't be written as pipe tables, so they
+    /// are written as grid tables regardless of the style
+    #[must_use]
+    pub fn write_table(
+        self, spec: &[ColSpec], head: &[Vec<String>], body: &[Vec<String>],
+    ) -> String {
+        let multiline = head.iter().chain(body).flatten().any(|c| c.contains('\n'));
+        match self {
+            Self::Pipe if !multiline && head.len() <= 1 =>
+                Self::write_pipe_table(spec, head.first(), body),
+            _ => Self::write_grid_table(spec, head, body),
+        }
+    }
+
+    fn write_pipe_table(
+        spec: &[ColSpec], head: Option<&Vec<String>>, body: &[Vec<String>],
+    ) -> String {
+        let empty = vec![String::new(); spec.len()];
+        let head = head.unwrap_or(&empty);
+        let widths: Vec<_> = (0..spec.len())
+            .map(|i| {
+                let cells = body.iter().filter_map(|r| r.get(i)).chain(head.get(i));
+                cells.map(|c| c.chars().count()).chain([3]).max().unwrap_or(3)
+            })
+            .collect();
+        let mut result = Self::write_pipe_row(head, &widths);
+        result.push_str("\n|");
+        for ((a, _), w) in spec.iter().zip(&widths) {
+            let (left, right) = match a {
+                Alignment::Left => (":", "-"),
+                Alignment::Right => ("-", ":"),
+                Alignment::Center => (":", ":"),
+                Alignment::Default => ("-", "-"),
+            };
+            result.push(' ');
+            result.push_str(left);
+            result.push_str(&"-".repeat(w - 2));
+            result.push_str(right);
+            result.push_str(" |");
+        }
+        for r in body {
+            result.push('\n');
+            result.push_str(&Self::write_pipe_row(r, &widths));
+        }
+        result
+    }
+
+    fn write_pipe_row(row: &[String], widths: &[usize]) -> String {
+        let mut result = String::from("|");
+        for (c, w) in row.iter().zip(widths) {
+            result.push(' ');
+            result.push_str(c);
+            result.push_str(&" ".repeat(w - c.chars().count()));
+            result.push_str(" |");
+        }
+        result
+    }
+
+    fn write_grid_table(spec: &[ColSpec], head: &[Vec<String>], body: &[Vec<String>]) -> String {
+        let widths: Vec<_> = (0..spec.len())
+            .map(|i| {
+                let cells = head.iter().chain(body).filter_map(|r| r.get(i));
+                let lines = cells.flat_map(|c| c.split('\n')).map(|l| l.chars().count());
+                lines.max().unwrap_or(0).max(1)
+            })
+            .collect();
+        let aligned_border = |fill: &str| {
+            let mut result = String::from("+");
+            for ((a, _), w) in spec.iter().zip(&widths) {
+                let left = matches!(a, Alignment::Left | Alignment::Center);
+                let right = matches!(a, Alignment::Right | Alignment::Center);
+                result.push_str(if left { ":" } else { fill });
+                result.push_str(&fill.repeat(*w));
+                result.push_str(if right { ":" } else { fill });
+                result.push('+');
+            }
+            result
+        };
+        let border = aligned_border("-").replace(':', "-");
+        let mut result = if head.is_empty() { aligned_border("-") } else { border.clone() };
+        for r in head {
+            result.push('\n');
+            result.push_str(&Self::write_grid_row(r, &widths));
+        }
+        if !head.is_empty() {
+            result.push('\n');
+            result.push_str(&aligned_border("="));
+        }
+        for r in body {
+            result.push('\n');
+            result.push_str(&Self::write_grid_row(r, &widths));
+            result.push('\n');
+            result.push_str(&border);
+        }
+        result
+    }
+
+    fn write_grid_row(row: &[String], widths: &[usize]) -> String {
+        let lines: Vec<Vec<_>> = row.iter().map(|c| c.split('\n').collect()).collect();
+        let height = lines.iter().map(Vec::len).max().unwrap_or(1);
+        let mut result = String::new();
+        for i in 0..height {
+            if i > 0 {
+                result.push('\n');
+            }
+            result.push('|');
+            for (c, w) in lines.iter().zip(widths) {
+                let line = c.get(i).copied().unwrap_or("");
+                result.push(' ');
+                result.push_str(line);
+                result.push_str(&" ".repeat(w - line.chars().count()));
+                result.push_str(" |");
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::*;
+
+    use super::*;
+
+    fn spec() -> Vec<ColSpec> {
+        vec![(Alignment::Left, ColWidth::default()), (Alignment::Right, ColWidth::default())]
+    }
+
+    fn row(cells: &[&str]) -> Vec<String> { cells.iter().map(|&c| c.to_owned()).collect() }
+
+    #[test]
+    fn pipe_table() {
+        let (head, body) = ([row(&["a", "b"])], [row(&["1", "2\\|3"])]);
+        let result = TableStyle::Pipe.write_table(&spec(), &head, &body);
+        assert_eq!(result, "| a   | b    |\n| :-- | ---: |\n| 1   | 2\\|3 |");
+    }
+
+    #[test]
+    fn grid_table() {
+        let (head, body) = ([row(&["a", "b"])], [row(&["1", "2"])]);
+        let result = TableStyle::Grid.write_table(&spec(), &head, &body);
+        assert_eq!(result, "+---+---+\n| a | b |\n+:==+==:+\n| 1 | 2 |\n+---+---+");
+    }
+
+    #[test]
+    fn pipe_falls_back_to_grid() {
+        let (head, body) = ([row(&["a", "b"])], [row(&["x\n\ny", "2"])]);
+        let result = TableStyle::Pipe.write_table(&spec(), &head, &body);
+        assert_eq!(
+            result,
+            "+---+---+\n| a | b |\n+:==+==:+\n| x | 2 |\n|   |   |\n| y |   |\n+---+---+"
+        );
+        let head = [row(&["a", "b"]), row(&["c", "d"])];
+        let result = TableStyle::Pipe.write_table(&spec(), &head, &[]);
+        assert_eq!(result, "+---+---+\n| a | b |\n| c | d |\n+:==+==:+");
+    }
+}
@@ -1,13 +1,79 @@
 //! Module containing the [`NativeReader`] type for reading [`Pandoc`] ast from JSON
 
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read};
+
+use derive_more::Display;
+
 use crate::ast::Pandoc;
 use crate::traits::AstReader;
 
 /// Deserialized a [`Pandoc`] ast representation from JSON for easy communication with Pandoc app
 pub struct NativeReader;
 
+/// Error when deserializing JSON, its message contains the line and column of the error
+#[derive(Debug, Display)]
+#[display(fmt = "Error while deserializing native JSON: {_0}")]
+pub struct ReadError(pub serde_json::Error);
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> { Some(&self.0) }
+}
+
 impl AstReader for NativeReader {
-    type ReadError = serde_json::Error;
+    type ReadError = ReadError;
+
+    fn read(self, str: &str) -> Result<Pandoc, Self::ReadError> {
+        serde_json::from_str(str.strip_prefix('\u{FEFF}').unwrap_or(str)).map_err(ReadError)
+    }
+
+    fn read_from<R: Read>(self, r: R) -> io::Result<Pandoc> {
+        let mut r = BufReader::new(r);
+        if r.fill_buf()?.starts_with("\u{FEFF}".as_bytes()) {
+            r.consume('\u{FEFF}'.len_utf8());
+        }
+        serde_json::from_reader(r).map_err(|e| {
+            if e.is_io() {
+                e.into()
+            } else {
+                io::Error::new(io::ErrorKind::InvalidData, ReadError(e))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn bom_is_stripped() {
+        let source = "\u{FEFF}{\"pandoc-api-version\":[1,23,1],\"meta\":{},\"blocks\":[]}";
+        let result = NativeReader.read(source).unwrap();
+        assert_eq!(result.pandoc_api_version, vec![1, 23, 1]);
+        assert!(result.blocks.is_empty());
+        let result = NativeReader.read_from(source.as_bytes()).unwrap();
+        assert_eq!(result.pandoc_api_version, vec![1, 23, 1]);
+    }
+
+    #[test]
+    fn read_from_cursor() {
+        let source =
+            "{\"pandoc-api-version\":[1,23,1],\"meta\":{},\"blocks\":[{\"t\":\"HorizontalRule\"}]}";
+        let result = NativeReader.read_from(Cursor::new(source)).unwrap();
+        assert_eq!(result, NativeReader.read(source).unwrap());
+        assert!(NativeReader.read_from(Cursor::new("{")).is_err());
+    }
 
-    fn read(self, str: &str) -> Result<Pandoc, Self::ReadError> { serde_json::from_str(str) }
+    #[test]
+    fn error_position() {
+        let source = "{\"pandoc-api-version\":[1,23,1],\n\"meta\":{},\n\"blocks\":[}";
+        let message = NativeReader.read(source).unwrap_err().to_string();
+        assert!(message.starts_with("Error while deserializing native JSON: "), "{message}");
+        assert!(message.contains("line 3 column 11"), "{message}");
+        let message = NativeReader.read_from(source.as_bytes()).unwrap_err().to_string();
+        assert!(message.contains("line 3 column 11"), "{message}");
+    }
 }
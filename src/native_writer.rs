@@ -1,16 +1,49 @@
 //! Module containing the [`NativeWriter`] type for writing [`Pandoc`] ast to JSON
 
+use std::error::Error;
+use std::io::{self, Write};
+
+use derive_more::Display;
+
 use crate::ast::Pandoc;
 use crate::traits::AstWriter;
 
 /// Serializes a [`Pandoc`] ast representation into JSON for easy communication with Pandoc app
 pub struct NativeWriter;
 
+/// Error when serializing JSON
+#[derive(Debug, Display)]
+#[display(fmt = "Error while serializing native JSON: {_0}")]
+pub struct WriteError(pub serde_json::Error);
+
+impl Error for WriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> { Some(&self.0) }
+}
+
 impl AstWriter for NativeWriter {
-    type WriteError = serde_json::Error;
+    type WriteError = WriteError;
 
     fn write(self, mut ast: Pandoc) -> Result<String, Self::WriteError> {
         ast.pandoc_api_version = vec![1, 23, 1];
-        serde_json::to_string(&ast)
+        serde_json::to_string(&ast).map_err(WriteError)
+    }
+
+    fn write_to<W: Write>(self, mut ast: Pandoc, w: &mut W) -> io::Result<()> {
+        ast.pandoc_api_version = vec![1, 23, 1];
+        Ok(serde_json::to_writer(w, &ast)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Block;
+
+    #[test]
+    fn write_to_buffer() {
+        let p = Pandoc { blocks: vec![Block::HorizontalRule], ..Default::default() };
+        let mut buffer = Vec::new();
+        NativeWriter.write_to(p.clone(), &mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), NativeWriter.write(p).unwrap());
     }
 }
@@ -1,6 +1,7 @@
 //! Module containing traits from reading to and writing from [`Pandoc`] ast
 
 use std::error::Error;
+use std::io::{self, Read, Write};
 
 use crate::ast::Pandoc;
 
@@ -13,6 +14,19 @@ pub trait AstReader {
     /// # Errors
     /// Returns an error when parsing was not successful
     fn read(self, str: &str) -> Result<Pandoc, Self::ReadError>;
+
+    /// Reads the whole content of `r` and parses it into a [`Pandoc`] ast representation. By
+    /// default the content is read into a string and passed to [`Self::read`], readers can
+    /// override it to parse the content while reading
+    /// # Errors
+    /// Returns an error when reading was not successful or the content is not valid UTF-8, parsing
+    /// errors are returned as [`io::ErrorKind::Other`]
+    fn read_from<R: Read>(self, mut r: R) -> io::Result<Pandoc>
+    where Self: Sized {
+        let mut source = String::new();
+        r.read_to_string(&mut source)?;
+        self.read(&source).map_err(|e| io::Error::other(e.to_string()))
+    }
 }
 
 /// Trait for writing a [`Pandoc`] ast representation into a file format
@@ -24,4 +38,39 @@ pub trait AstWriter {
     /// # Errors
     /// Returns an error when writing was not successful
     fn write(self, ast: Pandoc) -> Result<String, Self::WriteError>;
+
+    /// Writes a given [`Pandoc`] ast representation into a file format and writes the output to
+    /// `w`. By default the whole output of [`Self::write`] is written at once, writers can
+    /// override it to stream the output instead
+    /// # Errors
+    /// Returns an error when writing was not successful, errors of the writer are returned as
+    /// [`io::ErrorKind::Other`]
+    fn write_to<W: Write>(self, ast: Pandoc, w: &mut W) -> io::Result<()>
+    where Self: Sized {
+        let result = self.write(ast).map_err(|e| io::Error::other(e.to_string()))?;
+        w.write_all(result.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Block;
+    use crate::md_reader::MdReader;
+    use crate::typst_writer::TypstWriter;
+
+    #[test]
+    fn read_from_reader() {
+        let source = "# Heading\n\ntext";
+        let result = MdReader::new().read_from(io::Cursor::new(source)).unwrap();
+        assert_eq!(result, MdReader::new().read(source).unwrap());
+    }
+
+    #[test]
+    fn write_to_buffer() {
+        let p = Pandoc { blocks: vec![Block::HorizontalRule], ..Default::default() };
+        let mut buffer = Vec::new();
+        TypstWriter::new().write_to(p.clone(), &mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), TypstWriter::new().write(p).unwrap());
+    }
 }
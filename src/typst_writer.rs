@@ -1,34 +1,118 @@
 //! Module containing the [`TypstWriter`] type used for writing Typst
 
 use std::error::Error;
+use std::iter;
 
 use derive_more::Display;
 
-use crate::ast::{Alignment, Block, ColSpec, Inline, Pandoc, Row, TableBody, TableHead};
+use crate::ast::{
+    Alignment, Block, Citation, CitationMode, ColSpec, Inline, Pandoc, Row, TableBody, TableHead,
+};
 use crate::traits::AstWriter;
+use crate::alert::Alert;
+use crate::{language, url};
 
 /// Writes a [`Pandoc`] ast representation to Typst. For now only [`Block`] and `[Inline`] elements
 /// available in GitHub Flavoured Markdown are supported
-#[derive(Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct TypstWriter {
     result: String,
     in_emph: bool,
     in_strong: bool,
-    beginning: String,
+    in_link: bool,
+    indent: Vec<usize>,
+    line_start: bool,
+    item_start: bool,
+    options: TypstOptions,
+}
+
+/// Options changing the output of a [`TypstWriter`]
+#[derive(Debug, Clone)]
+pub struct TypstOptions {
+    /// Width of images passed to `image`, e.g. `80%`
+    pub image_width: String,
+    /// Whether to write a table of contents with `#outline()` before the content
+    pub toc: bool,
+    /// Number of levels added to the level of every header. Headers shifted past level 6 are
+    /// written as paragraphs of strong text
+    pub header_offset: usize,
+}
+
+impl Default for TypstOptions {
+    fn default() -> Self {
+        Self { image_width: String::from("100%"), toc: false, header_offset: 0 }
+    }
+}
+
+/// Builder for a [`TypstWriter`] returned by [`TypstWriter::builder`]
+#[derive(Debug, Clone, Default)]
+pub struct TypstWriterBuilder {
+    options: TypstOptions,
+}
+
+impl TypstWriterBuilder {
+    /// Sets [`TypstOptions::image_width`]
+    #[must_use]
+    pub fn image_width(mut self, width: impl Into<String>) -> Self {
+        self.options.image_width = width.into();
+        self
+    }
+
+    /// Sets [`TypstOptions::toc`]
+    #[must_use]
+    pub const fn toc(mut self, value: bool) -> Self {
+        self.options.toc = value;
+        self
+    }
+
+    /// Sets [`TypstOptions::header_offset`]
+    #[must_use]
+    pub const fn header_offset(mut self, value: usize) -> Self {
+        self.options.header_offset = value;
+        self
+    }
+
+    /// Creates a [`TypstWriter`] with the set options
+    #[must_use]
+    pub fn build(self) -> TypstWriter { TypstWriter::with_options(self.options) }
 }
 
 impl TypstWriter {
-    /// Creates a new [`TypstWriter`]
+    /// Creates a new [`TypstWriter`] with default [`TypstOptions`]
     #[must_use]
-    pub fn new() -> Self {
-        Self { result: String::new(), in_emph: false, in_strong: false, beginning: String::new() }
+    pub fn new() -> Self { Self::with_options(TypstOptions::default()) }
+
+    /// Creates a new [`TypstWriter`] with given [`TypstOptions`]
+    #[must_use]
+    pub const fn with_options(options: TypstOptions) -> Self {
+        Self {
+            result: String::new(),
+            in_emph: false,
+            in_strong: false,
+            in_link: false,
+            indent: Vec::new(),
+            line_start: true,
+            item_start: false,
+            options,
+        }
     }
+
+    /// Creates a [`TypstWriterBuilder`] starting with default [`TypstOptions`]
+    #[must_use]
+    pub fn builder() -> TypstWriterBuilder { TypstWriterBuilder::default() }
+}
+
+impl Default for TypstWriter {
+    fn default() -> Self { Self::new() }
 }
 
 impl AstWriter for TypstWriter {
     type WriteError = WriteError;
 
     fn write(mut self, ast: Pandoc) -> Result<String, Self::WriteError> {
+        if self.options.toc {
+            self.push_str("#outline()\n");
+        }
         self.write_blocks(ast.blocks)?;
         Ok(self.result)
     }
@@ -44,13 +128,37 @@ pub enum WriteError {
 impl Error for WriteError {}
 
 impl TypstWriter {
-    fn push_str(&mut self, str: &str) { self.result.push_str(str) }
+    fn push_str(&mut self, str: &str) {
+        self.result.push_str(str);
+        self.line_start = false;
+        self.item_start = false;
+    }
 
-    fn push(&mut self, c: char) { self.result.push(c) }
+    fn push(&mut self, c: char) {
+        self.result.push(c);
+        self.line_start = false;
+        self.item_start = false;
+    }
 
     fn new_line(&mut self) {
-        self.push('\n');
-        self.result.push_str(&self.beginning);
+        // The first block of a list item starts on the same line as the list marker
+        if self.item_start {
+            self.item_start = false;
+            return;
+        }
+        self.result.push('\n');
+        let width = self.indent.iter().sum();
+        self.result.extend(iter::repeat_n(' ', width));
+        self.line_start = true;
+    }
+
+    /// Runs a function with following lines indented by additional `width` spaces
+    fn indented<F>(&mut self, width: usize, f: F) -> Result<(), WriteError>
+    where F: FnOnce(&mut Self) -> Result<(), WriteError> {
+        self.indent.push(width);
+        let result = f(self);
+        self.indent.pop();
+        result
     }
 
     fn write_blocks(&mut self, blocks: Vec<Block>) -> Result<(), WriteError> {
@@ -68,7 +176,8 @@ impl TypstWriter {
                 self.write_inlines(p)?;
                 self.new_line();
             },
-            Block::CodeBlock((l, ..), t) => self.write_code_block(&l, &t),
+            Block::CodeBlock((_, c, _), t) =>
+                self.write_code_block(c.first().map_or("", String::as_str), &t),
             Block::BlockQuote(b) => {
                 self.new_line();
                 self.push_str("#quote(block: true)[");
@@ -79,7 +188,10 @@ impl TypstWriter {
             Block::OrderedList((s, ..), items) => self.write_ordered_list(s, items)?,
             Block::BulletList(items) => self.write_bullet_list(items)?,
             Block::Header(l, _, i) => self.write_header(l, i)?,
-            Block::HorizontalRule => self.push_str("\n---\n"),
+            Block::HorizontalRule => {
+                self.push_str("\n---\n");
+                self.line_start = true;
+            },
             Block::Table(_, _, s, TableHead(_, h), b, _) => self.write_table(s, h, b)?,
             Block::LineBlock(_) =>
                 return Err(WriteError::NotImplemented("Line block is not yet implemented")),
@@ -89,36 +201,29 @@ impl TypstWriter {
                 return Err(WriteError::NotImplemented("Definition list is not yet implemented")),
             Block::Figure(..) =>
                 return Err(WriteError::NotImplemented("Figure is not yet implemented")),
-            Block::Div(..) => return Err(WriteError::NotImplemented("Div is not yet implemented")),
+            Block::Div((_, c, _), mut b) => match Alert::new(&c, &mut b) {
+                Some(alert) => self.write_alert(alert, b)?,
+                None => return Err(WriteError::NotImplemented("Div is not yet implemented")),
+            },
         };
         Ok(())
     }
 
     fn write_code_block(&mut self, language: &str, content: &str) {
+        // The fence has to be longer than any run of backticks in the content
         let max = content
-            .lines()
-            .map(|s| {
-                let mut iter = s.chars();
-                let mut count = 0;
-                while iter.next() == Some('`') {
-                    count += 1;
-                }
-                match iter.next() {
-                    Some(_) => 0,
-                    None => count,
-                }
-            })
+            .split(|c| c != '`')
+            .map(str::len)
             .max()
-            .unwrap_or(0)
-            .max(3);
+            .map_or(3, |longest| (longest + 1).max(3));
         self.new_line();
         for _ in 0..max {
             self.push('`');
         }
         if !language.is_empty() {
-            self.push_str(language);
+            self.push_str(language::typst(language));
         }
-        for line in content.lines() {
+        for line in content.split('\n') {
             self.new_line();
             self.push_str(line);
         }
@@ -129,19 +234,33 @@ impl TypstWriter {
         self.new_line();
     }
 
+    /// Writes a GitHub alert as a block with a background and a left border colored by the alert
+    /// type
+    fn write_alert(&mut self, alert: Alert, content: Vec<Block>) -> Result<(), WriteError> {
+        let color = alert.typst_color;
+        self.new_line();
+        self.push_str(&format!(
+            "#block(fill: {color}.lighten(90%), stroke: (left: 2pt + {color}), inset: 8pt, \
+             width: 100%)[#strong["
+        ));
+        self.write_inlines(alert.title)?;
+        self.push(']');
+        self.write_blocks(content)?;
+        self.push(']');
+        self.new_line();
+        Ok(())
+    }
+
     fn write_ordered_list(&mut self, start: i32, items: Vec<Vec<Block>>) -> Result<(), WriteError> {
         self.new_line();
         for (item, i) in items.into_iter().zip(start..) {
             let parsed = i.to_string();
             self.push_str(&parsed);
             self.push_str(". ");
-            for _ in 0..parsed.len() + 2 {
-                self.beginning.push(' ');
-            }
-            self.write_blocks(item)?;
-            for _ in 0..parsed.len() + 2 {
-                self.beginning.pop();
-            }
+            self.line_start = true;
+            self.item_start = true;
+            self.indented(parsed.len() + 2, |w| w.write_blocks(item))?;
+            self.item_start = false;
             self.new_line();
         }
         self.new_line();
@@ -150,20 +269,27 @@ impl TypstWriter {
 
     fn write_bullet_list(&mut self, items: Vec<Vec<Block>>) -> Result<(), WriteError> {
         self.new_line();
-        self.beginning.push_str("  ");
         for item in items {
             self.push_str("- ");
-            self.write_blocks(item.clone())?;
+            self.line_start = true;
+            self.item_start = true;
+            self.indented(2, |w| w.write_blocks(item))?;
+            self.item_start = false;
+            self.new_line();
         }
-        self.beginning.pop();
-        self.beginning.pop();
         self.new_line();
         Ok(())
     }
 
+    /// Writes a header clamping its level to the 1..=6 range
     fn write_header(&mut self, level: i32, content: Vec<Inline>) -> Result<(), WriteError> {
+        let offset = self.options.header_offset;
+        let level = level.saturating_add(i32::try_from(offset).unwrap_or(i32::MAX));
+        if offset > 0 && level > 6 {
+            return self.write_block(Block::Para(vec![Inline::Strong(content)]));
+        }
         self.new_line();
-        for _ in 0..level {
+        for _ in 0..level.clamp(1, 6) {
             self.push('=');
         }
         self.push(' ');
@@ -180,7 +306,7 @@ impl TypstWriter {
         self.push_str("#table(\n");
         self.push_str("columns: ");
         self.push_str(&size.to_string());
-        self.push_str("\nalign: (col, row) => (");
+        self.push_str(",\nalign: (col, row) => (");
         for (c, _) in spec {
             match c {
                 Alignment::Left => self.push_str("left,"),
@@ -190,17 +316,50 @@ impl TypstWriter {
             }
         }
         self.push_str(").at(col),\n");
+        // For every column, the number of rows below the current one covered by a cell spanning
+        // multiple rows, which Typst places on its own
+        let mut covered = vec![0; size];
         for r in head.into_iter().chain(body.into_iter().next().into_iter().flat_map(|b| b.3)) {
-            for c in r.1.into_iter().take(size) {
+            let mut cells = r.1.into_iter();
+            let mut i = 0;
+            while i < size {
+                if covered[i] > 0 {
+                    covered[i] -= 1;
+                    i += 1;
+                    continue;
+                }
+                let Some(c) = cells.next() else {
+                    self.push_str("[],\n");
+                    i += 1;
+                    continue;
+                };
+                let span = usize::try_from(c.3 .0).unwrap_or(1).clamp(1, size - i);
+                let rows = usize::try_from(c.2 .0).unwrap_or(1).max(1);
+                covered[i..i + span].fill(rows - 1);
+                let mut spans = Vec::new();
+                if span > 1 {
+                    spans.push(format!("colspan: {span}"));
+                }
+                if rows > 1 {
+                    spans.push(format!("rowspan: {rows}"));
+                }
+                if !spans.is_empty() {
+                    self.push_str("table.cell(");
+                    self.push_str(&spans.join(", "));
+                    self.push(')');
+                }
                 self.push_str("[");
                 let mut c_iter = c.4.into_iter();
-                let (Some(Block::Plain(i)), None) = (c_iter.next(), c_iter.next()) else {
-                    return Err(WriteError::NotImplemented(
-                        "Tables with nested blocks aren't yet implemented",
-                    ));
-                };
-                self.write_inlines(i)?;
+                match (c_iter.next(), c_iter.next()) {
+                    (None, _) => {},
+                    (Some(Block::Plain(i)), None) => self.write_inlines(i)?,
+                    _ =>
+                        return Err(WriteError::NotImplemented(
+                            "Tables with nested blocks aren't yet implemented",
+                        )),
+                }
                 self.push_str("],\n");
+                i += span;
             }
         }
         self.push(')');
@@ -242,38 +401,47 @@ impl TypstWriter {
                 self.write_inlines(i)?;
                 self.push_str("]");
             },
-            Inline::Code(_, s) => {
-                let mut longest = 0;
-                let mut current = 0;
-                for c in s.chars() {
-                    if c == '`' {
-                        current += 1;
+            Inline::Code(_, s) => self.write_code(&s),
+            Inline::Space | Inline::SoftBreak => self.push(' '),
+            Inline::LineBreak => {
+                self.push('\\');
+                self.new_line();
+            },
+            Inline::Link(_, i, (u, t)) => {
+                self.push_str("#link(\"");
+                self.push_str(&url::percent_encode(&u));
+                // Typst shows the url without the `mailto:` prefix for links without content
+                let label = u.strip_prefix("mailto:").unwrap_or(&u);
+                if matches!(i.as_slice(), [Inline::Str(s)] if *s == u || s == label) {
+                    self.push_str("\");");
+                } else {
+                    self.push_str("\")[");
+                    if i.is_empty() {
+                        self.write_str(&t);
                     } else {
-                        longest = longest.max(current);
-                        current = 0;
+                        self.in_link = true;
+                        self.write_inlines(i)?;
+                        self.in_link = false;
                     }
+                    self.push(']');
                 }
-                for _ in 0..longest {
-                    self.push('`');
+            },
+            Inline::Image(_, alt, (u, _)) => {
+                // A figure is a block, so linked images are written without it
+                self.push_str(if self.in_link { "#image(\"" } else { "#figure(image(\"" });
+                if url::is_remote(&u) {
+                    self.push_str(&url::percent_encode(&u));
+                } else {
+                    self.write_string_literal(&u);
                 }
-                self.write_str(&s);
-                for _ in 0..longest {
-                    self.push('`');
+                self.push_str("\", width: ");
+                self.result.push_str(&self.options.image_width);
+                if !alt.is_empty() {
+                    self.push_str(", alt: \"");
+                    self.write_string_literal(&Inline::stringify(&alt));
+                    self.push('"');
                 }
-            },
-            Inline::Space | Inline::SoftBreak => self.push(' '),
-            Inline::LineBreak => self.push_str("\\\n"),
-            Inline::Link(_, _, (u, t)) => {
-                self.push_str("#link(");
-                self.push_str(&u);
-                self.push('[');
-                self.push_str(&t);
-                self.push(']');
-            },
-            Inline::Image(_, _, (u, _)) => {
-                self.push_str("#figure(image(\"");
-                self.push_str(&u);
-                self.push_str("\", width: 100%))");
+                self.push_str(if self.in_link { ")" } else { "))" });
             },
             Inline::Underline(_) =>
                 return Err(WriteError::NotImplemented("Underline is not yet implemented")),
@@ -285,8 +453,7 @@ impl TypstWriter {
                 return Err(WriteError::NotImplemented("Small caps is not yet implemented")),
             Inline::Quoted(..) =>
                 return Err(WriteError::NotImplemented("Quoted is not yet implemented")),
-            Inline::Cite(..) =>
-                return Err(WriteError::NotImplemented("Cite is not yet implemented")),
+            Inline::Cite(c, _) => self.write_citations(c)?,
             Inline::Math(..) =>
                 return Err(WriteError::NotImplemented("Math is not yet implemented")), //???
             Inline::RawInline(..) =>
@@ -295,24 +462,403 @@ impl TypstWriter {
                 return Err(WriteError::NotImplemented("Note is not yet implemented")),
             Inline::Span(..) =>
                 return Err(WriteError::NotImplemented("Span is not yet implemented")),
-            Inline::Temp(_) => todo!(),
-            Inline::None => todo!(),
+            Inline::Temp(_) | Inline::None =>
+                return Err(WriteError::NotImplemented("Reader-internal inline can't be written")),
+        }
+        Ok(())
+    }
+
+    /// Writes citations as `#cite(<id>)` calls rather than the `@id` shorthand, which can't be
+    /// followed directly by text and doesn't allow setting the form. [`CitationMode::AuthorInText`]
+    /// uses `form: "prose"`, [`CitationMode::SuppressAuthor`] uses `form: "year"` and a suffix is
+    /// passed as the supplement
+    fn write_citations(&mut self, citations: Vec<Citation>) -> Result<(), WriteError> {
+        for c in citations {
+            self.push_str("#cite(<");
+            self.push_str(&c.id);
+            self.push('>');
+            match c.mode {
+                CitationMode::NormalCitation => {},
+                CitationMode::AuthorInText => self.push_str(", form: \"prose\""),
+                CitationMode::SuppressAuthor => self.push_str(", form: \"year\""),
+            }
+            let mut suffix = c.suffix.into_iter().peekable();
+            suffix.next_if(|i| *i == Inline::Str(String::from(",")));
+            suffix.next_if(|i| *i == Inline::Space);
+            if suffix.peek().is_some() {
+                self.push_str(", supplement: [");
+                self.write_inlines(suffix.collect())?;
+                self.push(']');
+            }
+            self.push(')');
         }
         Ok(())
     }
 
+    /// Writes inline code verbatim between backticks, code containing backticks is written as a
+    /// string passed to `raw` as backticks cannot be escaped inside raw text
+    fn write_code(&mut self, code: &str) {
+        if code.contains('`') {
+            self.push_str("#raw(\"");
+            self.write_string_literal(code);
+            self.push_str("\");");
+        } else {
+            self.push('`');
+            self.push_str(code);
+            self.push('`');
+        }
+    }
+
+    /// Writes content of a string literal escaping backslashes and quotes
+    fn write_string_literal(&mut self, str: &str) {
+        for c in str.chars() {
+            if matches!(c, '\\' | '"') {
+                self.push('\\');
+            }
+            self.push(c);
+        }
+    }
+
     fn write_str(&mut self, str: &str) {
         for c in str.chars() {
             self.write_char(c);
         }
     }
 
+    /// Writes a char escaping it if needed. Markup chars are always escaped, chars that only start
+    /// a list, an enumeration or a heading are escaped only at the beginning of a line. Escaping
+    /// doesn't depend on enclosing emphasis, as markup chars would end it or start nested markup
     fn write_char(&mut self, c: char) {
         let special =
             ['\\', '{', '}', '[', ']', '(', ')', '#', '$', '%', '^', '*', '_', '&', '~', '`'];
-        if special.contains(&c) || c.is_ascii_digit() {
+        let line_start_special = matches!(c, '-' | '+' | '=') || c.is_ascii_digit();
+        if special.contains(&c) || (self.line_start && line_start_special) {
             self.push('\\');
         }
         self.push(c);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::ast::*;
+    use crate::md_reader::MdReader;
+    use crate::traits::AstReader;
+
+    use super::*;
+
+    fn write(blocks: Vec<Block>) -> String {
+        let p = Pandoc { pandoc_api_version: Vec::new(), meta: Meta::default(), blocks };
+        TypstWriter::new().write(p).unwrap()
+    }
+
+    fn str(s: &str) -> Inline { Inline::Str(String::from(s)) }
+
+    #[test]
+    fn header_levels() {
+        let result = write(vec![Block::Header(8, attr_empty(), vec![str("a")])]);
+        assert_eq!(result.trim(), "====== a");
+        let result = write(vec![Block::Header(0, attr_empty(), vec![str("a")])]);
+        assert_eq!(result.trim(), "= a");
+    }
+
+    #[test]
+    fn header_offset() {
+        let header = |l| Block::Header(l, attr_empty(), vec![str("a")]);
+        let p = Pandoc { blocks: vec![header(1), header(6)], ..Default::default() };
+        let result = TypstWriter::builder().header_offset(1).build().write(p).unwrap();
+        assert_eq!(result.trim(), "== a\n\n*a*");
+    }
+
+    #[test]
+    fn mid_word_special_chars() {
+        let result = write(vec![Block::Plain(vec![
+            str("a*b_c"),
+            Inline::Space,
+            Inline::Strong(vec![str("x")]),
+            Inline::Space,
+            Inline::Emph(vec![str("y")]),
+        ])]);
+        assert_eq!(result, "a\\*b\\_c *x* _y_");
+    }
+
+    #[test]
+    fn special_chars_in_emphasis() {
+        let result = write(vec![Block::Plain(vec![
+            Inline::Emph(vec![str("a*b_c")]),
+            Inline::Space,
+            Inline::Strong(vec![str("x_y"), Inline::Emph(vec![str("z*")])]),
+            Inline::Space,
+            Inline::Emph(vec![Inline::Emph(vec![str("-n")])]),
+        ])]);
+        assert_eq!(result, "_a\\*b\\_c_ *x\\_y_z\\*_* _-n_");
+    }
+
+    #[test]
+    fn line_start_chars() {
+        let result = write(vec![Block::Para(vec![str("-a"), Inline::Space, str("b-c+d=1")])]);
+        assert_eq!(result.trim(), "\\-a b-c+d=1");
+        let result = write(vec![Block::Para(vec![str("+a")]), Block::Para(vec![str("=b")])]);
+        assert!(result.contains("\\+a"));
+        assert!(result.contains("\\=b"));
+        let result = write(vec![Block::Para(vec![str("1. not a list")])]);
+        assert_eq!(result.trim(), "\\1. not a list");
+    }
+
+    #[test]
+    fn line_start_chars_after_line_break() {
+        let mut inlines = vec![str("a")];
+        for s in ["= b", "- c", "+ d", "1. e"] {
+            inlines.extend([Inline::LineBreak, str(s)]);
+        }
+        let result = write(vec![Block::BulletList(vec![vec![Block::Plain(inlines)]])]);
+        assert_eq!(result.trim(), "- a\\\n  \\= b\\\n  \\- c\\\n  \\+ d\\\n  \\1. e");
+    }
+
+    #[test]
+    fn paragraph_starting_with_hash() {
+        let result = write(vec![Block::Para(vec![
+            str("#not"),
+            Inline::Space,
+            str("a"),
+            Inline::Space,
+            str("heading"),
+        ])]);
+        assert_eq!(result.trim(), "\\#not a heading");
+        let pandoc = MdReader::new().read("\\#not a heading").unwrap();
+        assert_eq!(TypstWriter::new().write(pandoc).unwrap().trim(), "\\#not a heading");
+    }
+
+    #[test]
+    fn link_url_encoding() {
+        let link = Inline::Link(
+            attr_empty(),
+            vec![str("x")],
+            (String::from("https://x.com/a b\""), String::new()),
+        );
+        let result = write(vec![Block::Plain(vec![link])]);
+        assert_eq!(result, "#link(\"https://x.com/a%20b%22\")[x]");
+    }
+
+    #[test]
+    fn autolink() {
+        let link = |label: &str, url: &str| {
+            Inline::Link(attr_empty(), vec![str(label)], (String::from(url), String::new()))
+        };
+        let result = write(vec![Block::Plain(vec![link("https://a.b", "https://a.b")])]);
+        assert_eq!(result, "#link(\"https://a.b\");");
+        let result = write(vec![Block::Plain(vec![link("a@b.c", "mailto:a@b.c")])]);
+        assert_eq!(result, "#link(\"mailto:a@b.c\");");
+        let result = write(vec![Block::Plain(vec![link("www.a.b", "http://www.a.b")])]);
+        assert_eq!(result, "#link(\"http://www.a.b\")[www.a.b]");
+        let pandoc = MdReader::new().read("see https://a.b.").unwrap();
+        let result = TypstWriter::new().write(pandoc).unwrap();
+        assert_eq!(result.trim(), "see #link(\"https://a.b\");.");
+    }
+
+    #[test]
+    fn linked_image() {
+        let pandoc = MdReader::new().read("[![alt](img.png)](https://x.com)").unwrap();
+        let target = |url: &str| (String::from(url), String::new());
+        let image = Inline::Image(attr_empty(), vec![str("alt")], target("img.png"));
+        let link = Inline::Link(attr_empty(), vec![image], target("https://x.com"));
+        assert_eq!(pandoc.blocks, vec![Block::Para(vec![link])]);
+        let result = TypstWriter::new().write(pandoc).unwrap();
+        let expected = "#link(\"https://x.com\")[#image(\"img.png\", width: 100%, alt: \"alt\")]";
+        assert_eq!(result.trim(), expected);
+    }
+
+    #[test]
+    fn image_width() {
+        let p = Pandoc {
+            pandoc_api_version: Vec::new(),
+            meta: Meta::default(),
+            blocks: vec![Block::Plain(vec![Inline::Image(
+                attr_empty(),
+                Vec::new(),
+                (String::from("image.png"), String::new()),
+            )])],
+        };
+        let options = TypstOptions { image_width: String::from("80%"), ..Default::default() };
+        let result = TypstWriter::with_options(options).write(p).unwrap();
+        assert_eq!(result, "#figure(image(\"image.png\", width: 80%))");
+    }
+
+    #[test]
+    fn image_alt() {
+        let image = Inline::Image(
+            attr_empty(),
+            vec![str("a"), Inline::Space, str("\"b\"")],
+            (String::from("image.png"), String::new()),
+        );
+        let result = write(vec![Block::Plain(vec![image])]);
+        assert_eq!(result, "#figure(image(\"image.png\", width: 100%, alt: \"a \\\"b\\\"\"))");
+    }
+
+    #[test]
+    fn tight_list() {
+        let items = vec![vec![Block::Plain(vec![str("a")])], vec![Block::Plain(vec![str("b")])]];
+        let result = write(vec![Block::BulletList(items)]);
+        assert_eq!(result.trim(), "- a\n- b");
+    }
+
+    #[test]
+    fn multi_paragraph_item() {
+        let items = vec![
+            vec![Block::Para(vec![str("a")]), Block::Para(vec![str("b")])],
+            vec![Block::Para(vec![str("c")])],
+        ];
+        let result = write(vec![Block::BulletList(items.clone())]);
+        assert_eq!(result.trim_end(), "\n- a\n  \n  b\n  \n- c");
+        let style = (1, ListNumberStyle::Decimal, ListNumberDelim::Period);
+        let result = write(vec![Block::OrderedList(style, items)]);
+        assert_eq!(result.trim_end(), "\n1. a\n   \n   b\n   \n2. c");
+    }
+
+    #[test]
+    fn citations() {
+        let citation = |id: &str, mode: CitationMode, suffix: Vec<Inline>| Citation {
+            id: String::from(id),
+            prefix: Vec::new(),
+            suffix,
+            mode,
+            note_num: 0,
+            hash: 0,
+        };
+        let cite = |c: Vec<Citation>| vec![Block::Plain(vec![Inline::Cite(c, Vec::new())])];
+        let result = write(cite(vec![citation("foo", CitationMode::NormalCitation, Vec::new())]));
+        assert_eq!(result, "#cite(<foo>)");
+        let suffix = vec![str(","), Inline::Space, str("p."), Inline::Space, str("5")];
+        let result = write(cite(vec![
+            citation("a", CitationMode::AuthorInText, Vec::new()),
+            citation("b", CitationMode::SuppressAuthor, suffix),
+        ]));
+        let expected = "#cite(<a>, form: \"prose\")#cite(<b>, form: \"year\", supplement: [p. 5])";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn empty_cells() {
+        let cell = |s: &str| {
+            Cell(attr_empty(), Alignment::Default, RowSpan(1), ColSpan(1), vec![Block::Plain(
+                vec![str(s)],
+            )])
+        };
+        let rows = vec![
+            Row(attr_empty(), vec![cell("1")]),
+            Row(attr_empty(), vec![Cell::default(), Cell::default()]),
+        ];
+        let result = write(vec![Block::Table(
+            attr_empty(),
+            Caption::default(),
+            vec![(Alignment::Default, ColWidth::ColWidthDefault); 2],
+            TableHead(attr_empty(), vec![Row(attr_empty(), vec![cell("a"), cell("b")])]),
+            vec![TableBody(attr_empty(), RowHeadColumns(0), Vec::new(), rows)],
+            TableFoot::default(),
+        )]);
+        let expected = "#table(\ncolumns: 2,\nalign: (col, row) => (auto,auto,).at(col),\n\
+                        [a],\n[b],\n[1],\n[],\n[],\n[],\n)";
+        assert_eq!(result.trim(), expected);
+    }
+
+    #[test]
+    fn cell_spans() {
+        let cell = |s: &str, rows, cols| {
+            let content = vec![Block::Plain(vec![str(s)])];
+            Cell(attr_empty(), Alignment::Default, RowSpan(rows), ColSpan(cols), content)
+        };
+        let rows = vec![
+            Row(attr_empty(), vec![cell("a", 2, 1), cell("b", 1, 1)]),
+            Row(attr_empty(), vec![cell("c", 1, 1)]),
+        ];
+        let result = write(vec![Block::Table(
+            attr_empty(),
+            Caption::default(),
+            vec![(Alignment::Default, ColWidth::ColWidthDefault); 2],
+            TableHead(attr_empty(), vec![Row(attr_empty(), vec![cell("h", 1, 2)])]),
+            vec![TableBody(attr_empty(), RowHeadColumns(0), Vec::new(), rows)],
+            TableFoot::default(),
+        )]);
+        let expected = "#table(\ncolumns: 2,\nalign: (col, row) => (auto,auto,).at(col),\n\
+                        table.cell(colspan: 2)[h],\ntable.cell(rowspan: 2)[a],\n[b],\n[c],\n)";
+        assert_eq!(result.trim(), expected);
+    }
+
+    #[test]
+    fn code_block_verbatim() {
+        for content in ["a\n", "a\n\n", "a", "", "*x* _y_ #z \\ $m$\n  @ref <l>"] {
+            let result = write(vec![Block::CodeBlock(attr_empty(), String::from(content))]);
+            let inner = result.strip_prefix("\n```\n").unwrap().strip_suffix("\n```\n").unwrap();
+            assert_eq!(inner, content);
+        }
+    }
+
+    #[test]
+    fn code_block_fence_length() {
+        for (content, fence) in [("`````", "``````"), ("a ````` b", "``````"), ("``", "```")] {
+            let result = write(vec![Block::CodeBlock(attr_empty(), String::from(content))]);
+            assert_eq!(result, format!("\n{fence}\n{content}\n{fence}\n"));
+        }
+    }
+
+    #[test]
+    fn code_block_language() {
+        let code = |l: &str| {
+            Block::CodeBlock((String::new(), vec![String::from(l)], Vec::new()), String::from("x"))
+        };
+        assert_eq!(write(vec![code("c++")]), "\n```cpp\nx\n```\n");
+        assert_eq!(write(vec![code("rust")]), "\n```rust\nx\n```\n");
+    }
+
+    #[test]
+    fn toc() {
+        let header = Block::Header(1, attr_empty(), vec![str("a")]);
+        let p = Pandoc { blocks: vec![header], ..Default::default() };
+        let result = TypstWriter::builder().toc(true).build().write(p.clone()).unwrap();
+        assert_eq!(result, "#outline()\n\n= a\n");
+        assert_eq!(TypstWriter::new().write(p).unwrap(), "\n= a\n");
+    }
+
+    #[test]
+    fn alert() {
+        let note = (String::new(), vec![String::from("note")], Vec::new());
+        let result = write(vec![Block::Div(note, vec![Block::Para(vec![str("a")])])]);
+        assert_eq!(
+            result,
+            "\n#block(fill: blue.lighten(90%), stroke: (left: 2pt + blue), inset: 8pt, width: \
+             100%)[#strong[Note]\na\n]\n"
+        );
+    }
+
+    #[test]
+    fn nested_list_indent() {
+        let plain = |s| vec![Block::Plain(vec![str(s)])];
+        let bullets = Block::BulletList(vec![plain("b"), vec![
+            Block::Plain(vec![str("c")]),
+            Block::BulletList(vec![plain("d")]),
+        ]]);
+        let first = vec![Block::Plain(vec![str("a")]), bullets];
+        let list = Block::OrderedList(new_list_attributes(9, '.'), vec![first, plain("e")]);
+        let result = write(vec![list]);
+        let lines: Vec<_> = result.lines().filter(|l| !l.trim().is_empty()).collect();
+        assert_eq!(lines, ["9. a", "   - b", "   - c", "     - d", "10. e"]);
+    }
+
+    #[test]
+    fn bullet_list_items() {
+        let code = Block::CodeBlock(attr_empty(), String::from("x\ny"));
+        let items = vec![vec![Block::Plain(vec![str("a")]), code], vec![Block::Plain(vec![
+            Inline::Strong(vec![str("b")]),
+        ])]];
+        let result = write(vec![Block::BulletList(items)]);
+        assert_eq!(result.trim_end(), "\n- a\n  ```\n  x\n  y\n  ```\n  \n- *b*");
+    }
+
+    #[test]
+    fn code_verbatim() {
+        let code = |s: &str| write(vec![Block::Plain(vec![Inline::Code(attr_empty(), s.into())])]);
+        assert_eq!(code("a*b"), "`a*b`");
+        assert_eq!(code("#x \\ _y_ $z$"), "`#x \\ _y_ $z$`");
+        assert_eq!(code("a`b\"c\\"), "#raw(\"a`b\\\"c\\\\\");");
+    }
+}
@@ -0,0 +1,39 @@
+//! Module containing helpers for writing urls
+
+use std::fmt::Write;
+
+/// Percent-encodes chars that are not allowed in a url: whitespace, control chars, non-ASCII chars,
+/// quotes, angle brackets, braces, backslashes, backticks, carets and pipes. Already encoded
+/// sequences and reserved chars are left unchanged
+pub fn percent_encode(url: &str) -> String {
+    let mut result = String::with_capacity(url.len());
+    for c in url.chars() {
+        if c.is_ascii_graphic() && !matches!(c, '"' | '<' | '>' | '\\' | '^' | '`' | '{' | '|' | '}')
+        {
+            result.push(c);
+        } else {
+            let mut buffer = [0; 4];
+            for b in c.encode_utf8(&mut buffer).bytes() {
+                // Writing to a String never fails
+                _ = write!(result, "%{b:02X}");
+            }
+        }
+    }
+    result
+}
+
+/// Checks if an image source is a url rather than a path to a local file
+pub fn is_remote(source: &str) -> bool { source.contains("://") }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding() {
+        assert_eq!(percent_encode("a b"), "a%20b");
+        assert_eq!(percent_encode("https://x.com/?q=a&b#c"), "https://x.com/?q=a&b#c");
+        assert_eq!(percent_encode("a%20b"), "a%20b");
+        assert_eq!(percent_encode("{ż}"), "%7B%C5%BC%7D");
+    }
+}
@@ -0,0 +1,137 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+#[cfg(feature = "native")]
+const FIGURE: &str = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[
+    {"t":"Figure","c":[["",[],[]],[null,[]],[]]}
+]}"#;
+
+fn run(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_md_converter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    String::from_utf8(child.wait_with_output().unwrap().stdout).unwrap()
+}
+
+#[test]
+#[cfg(feature = "native")]
+fn check_reports_unsupported_element() {
+    let output = run(&["--check", "-f", "native", "-t", "latex"], FIGURE);
+    assert_eq!(output, "Conversion to latex would fail:\nFigure is not yet implemented\n");
+}
+
+#[test]
+#[cfg(feature = "native")]
+fn multiple_native_files() {
+    let first = std::env::temp_dir().join(format!("md_converter_{}_a.json", std::process::id()));
+    let second = std::env::temp_dir().join(format!("md_converter_{}_b.json", std::process::id()));
+    let document = |text| {
+        format!(
+            r#"{{"pandoc-api-version":[1,23,1],"meta":{{}},"blocks":[
+                {{"t":"Para","c":[{{"t":"Str","c":"{}"}}]}}
+            ]}}"#,
+            text
+        )
+    };
+    std::fs::write(&first, document("First")).unwrap();
+    std::fs::write(&second, document("Second")).unwrap();
+    let files = [first.to_str().unwrap(), second.to_str().unwrap()];
+    let output = run(&["-f", "native", "-t", "typst", files[0], files[1]], "");
+    std::fs::remove_file(&first).unwrap();
+    std::fs::remove_file(&second).unwrap();
+    assert_eq!(output.trim(), "First\n\nSecond");
+}
+
+#[test]
+fn check_does_not_write_output() {
+    let output = run(&["--check", "-f", "gfm", "-t", "latex"], "# Heading\n");
+    assert_eq!(output, "Conversion to latex would succeed\n");
+}
+
+#[test]
+#[cfg(feature = "native")]
+fn reader_error() {
+    let output = run(&["-f", "native", "-t", "latex"], "not json");
+    assert!(output.starts_with("Failed to read input as native:\n"));
+}
+
+#[test]
+#[cfg(feature = "native")]
+fn writer_error() {
+    let output = run(&["-f", "native", "-t", "typst"], FIGURE);
+    assert_eq!(output, "Failed to write output as typst:\nFigure is not yet implemented\n");
+}
+
+#[test]
+fn latex_without_native() {
+    let output = run(&["-f", "gfm", "-t", "latex"], "# Heading\n");
+    assert!(output.contains("\\section{Heading}"));
+}
+
+#[test]
+fn format_names_ignore_case() {
+    let output = run(&["-f", "GFM", "-t", "Typst"], "# Heading\n");
+    assert_eq!(output.trim(), "= Heading");
+}
+
+#[test]
+fn gzip_round_trip() {
+    let path = std::env::temp_dir().join(format!("md_converter_{}.md.gz", std::process::id()));
+    let file = path.to_str().unwrap();
+    let source = "# Heading\n\nSome *text*\n";
+    let mut encoder = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+    encoder.write_all(source.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+    let expected = run(&["-f", "gfm", "-t", "typst"], source);
+    assert_eq!(run(&["-f", "gfm", "-t", "typst", file], ""), expected);
+    let output = run(&["-f", "gfm", "-t", "typst", "-o", file], source);
+    assert_eq!(output, format!("Saved result to: {file}\n"));
+    assert!(std::fs::read(&path).unwrap().starts_with(&[0x1f, 0x8b]));
+    let mut content = String::new();
+    GzDecoder::new(File::open(&path).unwrap()).read_to_string(&mut content).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(format!("{content}\n"), expected);
+}
+
+#[test]
+fn multiple_files() {
+    let first = std::env::temp_dir().join(format!("md_converter_{}_a.md", std::process::id()));
+    let second = std::env::temp_dir().join(format!("md_converter_{}_b.md", std::process::id()));
+    std::fs::write(&first, "# First\n\nSome text").unwrap();
+    std::fs::write(&second, "Other text\n").unwrap();
+    let files = [first.to_str().unwrap(), second.to_str().unwrap()];
+    let output = run(&["-f", "gfm", "-t", "typst", files[0], files[1]], "");
+    std::fs::remove_file(&first).unwrap();
+    std::fs::remove_file(&second).unwrap();
+    assert_eq!(output.trim(), "= First\n\nSome text\n\nOther text");
+}
+
+#[test]
+fn toc() {
+    let output = run(&["--toc", "-f", "gfm", "-t", "latex"], "# Heading\n");
+    assert!(output.contains("\\begin{document}\n\\tableofcontents\n"));
+}
+
+#[test]
+fn shift_heading_level() {
+    let output = run(&["--shift-heading-level-by", "2", "-f", "gfm", "-t", "typst"], "# Heading\n");
+    assert_eq!(output.trim(), "=== Heading");
+}
+
+#[test]
+#[cfg(feature = "native")]
+fn ordered_list_delimiter() {
+    let output = run(&["-f", "gfm", "-t", "native"], "1) a\n2) b\n");
+    assert!(output.contains(r#"[1,{"t":"Decimal"},{"t":"OneParen"}]"#));
+    let output = run(&["-f", "gfm", "-t", "native"], "1. a\n2. b\n");
+    assert!(output.contains(r#"[1,{"t":"Decimal"},{"t":"Period"}]"#));
+}
@@ -0,0 +1,14 @@
+//! Reads a Pandoc JSON fixture covering the elements supported by [`LatexWriter`] with
+//! [`NativeReader`] and compares the written LaTeX with the expected output
+#![cfg(feature = "native")]
+
+use md_converter::latex_writer::LatexWriter;
+use md_converter::native_reader::NativeReader;
+use md_converter::traits::{AstReader, AstWriter};
+
+#[test]
+fn native_to_latex() {
+    let pandoc = NativeReader.read(include_str!("fixtures/elements.json")).unwrap();
+    let result = LatexWriter::new().write(pandoc).unwrap();
+    assert_eq!(result.trim_end(), include_str!("fixtures/elements.tex").trim_end());
+}
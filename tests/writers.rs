@@ -0,0 +1,173 @@
+use md_converter::ast::*;
+use md_converter::latex_writer::LatexWriter;
+use md_converter::traits::AstWriter;
+use md_converter::typst_writer::TypstWriter;
+use proptest::collection::vec;
+use proptest::option::of;
+use proptest::prelude::*;
+
+fn text() -> BoxedStrategy<String> { "[ -~\n\t\u{a0}ą€😀]{0,8}".boxed() }
+
+fn attr() -> BoxedStrategy<Attr> {
+    (text(), vec(text(), 0..3), vec((text(), text()), 0..2)).boxed()
+}
+
+fn alignment() -> BoxedStrategy<Alignment> {
+    prop_oneof![
+        Just(Alignment::Left),
+        Just(Alignment::Right),
+        Just(Alignment::Center),
+        Just(Alignment::Default),
+    ]
+    .boxed()
+}
+
+fn citation(inlines: BoxedStrategy<Vec<Inline>>) -> BoxedStrategy<Citation> {
+    let mode = prop_oneof![
+        Just(CitationMode::AuthorInText),
+        Just(CitationMode::SuppressAuthor),
+        Just(CitationMode::NormalCitation),
+    ];
+    (text(), inlines.clone(), inlines, mode, any::<i32>(), any::<i32>())
+        .prop_map(|(id, prefix, suffix, mode, note_num, hash)| Citation {
+            id,
+            prefix,
+            suffix,
+            mode,
+            note_num,
+            hash,
+        })
+        .boxed()
+}
+
+fn inline() -> BoxedStrategy<Inline> {
+    let math = prop_oneof![Just(MathType::InlineMath), Just(MathType::DisplayMath)];
+    let leaf = prop_oneof![
+        text().prop_map(Inline::Str),
+        Just(Inline::Space),
+        Just(Inline::SoftBreak),
+        Just(Inline::LineBreak),
+        (attr(), text()).prop_map(|(a, t)| Inline::Code(a, t)),
+        (math, text()).prop_map(|(m, t)| Inline::Math(m, t)),
+        (text(), text()).prop_map(|(f, t)| Inline::RawInline(Format(f), t)),
+        text().prop_map(Inline::Temp),
+        Just(Inline::None),
+    ];
+    leaf.prop_recursive(3, 24, 4, |inner| {
+        let inlines = vec(inner, 0..4).boxed();
+        let quote = prop_oneof![Just(QuoteType::SingleQuote), Just(QuoteType::DoubleQuote)];
+        let target = (text(), text());
+        prop_oneof![
+            inlines.clone().prop_map(Inline::Emph),
+            inlines.clone().prop_map(Inline::Underline),
+            inlines.clone().prop_map(Inline::Strong),
+            inlines.clone().prop_map(Inline::Strikeout),
+            inlines.clone().prop_map(Inline::Superscript),
+            inlines.clone().prop_map(Inline::Subscript),
+            inlines.clone().prop_map(Inline::SmallCaps),
+            (quote, inlines.clone()).prop_map(|(q, i)| Inline::Quoted(q, i)),
+            (vec(citation(inlines.clone()), 0..3), inlines.clone())
+                .prop_map(|(c, i)| Inline::Cite(c, i)),
+            (attr(), inlines.clone(), target.clone()).prop_map(|(a, i, t)| Inline::Link(a, i, t)),
+            (attr(), inlines.clone(), target).prop_map(|(a, i, t)| Inline::Image(a, i, t)),
+            inlines.clone().prop_map(|i| Inline::Note(vec![Block::Para(i)])),
+            (attr(), inlines).prop_map(|(a, i)| Inline::Span(a, i)),
+        ]
+    })
+    .boxed()
+}
+
+fn caption(blocks: BoxedStrategy<Vec<Block>>) -> BoxedStrategy<Caption> {
+    (of(vec(inline(), 0..3)), blocks).prop_map(|(s, b)| Caption(s, b)).boxed()
+}
+
+fn row(blocks: BoxedStrategy<Vec<Block>>) -> BoxedStrategy<Row> {
+    let cell = (attr(), alignment(), any::<i32>(), any::<i32>(), blocks)
+        .prop_map(|(a, al, r, c, b)| Cell(a, al, RowSpan(r), ColSpan(c), b));
+    (attr(), vec(cell, 0..4)).prop_map(|(a, c)| Row(a, c)).boxed()
+}
+
+fn table(blocks: BoxedStrategy<Vec<Block>>) -> BoxedStrategy<Block> {
+    let width = prop_oneof![
+        Just(ColWidth::ColWidthDefault),
+        any::<f64>().prop_map(ColWidth::ColWidth),
+    ];
+    let rows = vec(row(blocks.clone()), 0..3).boxed();
+    let body = (attr(), any::<i32>(), rows.clone(), rows.clone())
+        .prop_map(|(a, h, r1, r2)| TableBody(a, RowHeadColumns(h), r1, r2));
+    (
+        attr(),
+        caption(blocks),
+        vec((alignment(), width), 0..4),
+        (attr(), rows.clone()).prop_map(|(a, r)| TableHead(a, r)),
+        vec(body, 0..2),
+        (attr(), rows).prop_map(|(a, r)| TableFoot(a, r)),
+    )
+        .prop_map(|(a, c, s, h, b, f)| Block::Table(a, c, s, h, b, f))
+        .boxed()
+}
+
+fn block() -> BoxedStrategy<Block> {
+    let inlines = vec(inline(), 0..4).boxed();
+    let leaf = prop_oneof![
+        inlines.clone().prop_map(Block::Plain),
+        inlines.clone().prop_map(Block::Para),
+        vec(inlines.clone(), 0..3).prop_map(Block::LineBlock),
+        (attr(), text()).prop_map(|(a, t)| Block::CodeBlock(a, t)),
+        (text(), text()).prop_map(|(f, t)| Block::RawBlock(Format(f), t)),
+        (any::<i32>(), attr(), inlines.clone()).prop_map(|(l, a, i)| Block::Header(l, a, i)),
+        Just(Block::HorizontalRule),
+    ];
+    leaf.prop_recursive(3, 24, 4, move |inner| {
+        let blocks = vec(inner, 0..3).boxed();
+        let style = prop_oneof![
+            Just(ListNumberStyle::DefaultStyle),
+            Just(ListNumberStyle::Example),
+            Just(ListNumberStyle::Decimal),
+            Just(ListNumberStyle::LowerRoman),
+            Just(ListNumberStyle::UpperRoman),
+            Just(ListNumberStyle::LowerAlpha),
+            Just(ListNumberStyle::UpperAlpha),
+        ];
+        let delim = prop_oneof![
+            Just(ListNumberDelim::DefaultDelim),
+            Just(ListNumberDelim::Period),
+            Just(ListNumberDelim::OneParen),
+            Just(ListNumberDelim::TwoParens),
+        ];
+        let definitions = (inlines.clone(), vec(blocks.clone(), 0..2));
+        prop_oneof![
+            blocks.clone().prop_map(Block::BlockQuote),
+            ((any::<i32>(), style, delim), vec(blocks.clone(), 0..3))
+                .prop_map(|(a, i)| Block::OrderedList(a, i)),
+            vec(blocks.clone(), 0..3).prop_map(Block::BulletList),
+            vec(definitions, 0..3).prop_map(Block::DefinitionList),
+            table(blocks.clone()),
+            (attr(), caption(blocks.clone()), blocks.clone())
+                .prop_map(|(a, c, b)| Block::Figure(a, c, b)),
+            (attr(), blocks).prop_map(|(a, b)| Block::Div(a, b)),
+        ]
+    })
+    .boxed()
+}
+
+fn pandoc() -> BoxedStrategy<Pandoc> {
+    vec(block(), 0..4).prop_map(|blocks| Pandoc { blocks, ..Default::default() }).boxed()
+}
+
+proptest! {
+    #[test]
+    fn writers_do_not_panic(p in pandoc()) {
+        let _ = LatexWriter::new().write(p.clone());
+        let _ = TypstWriter::new().write(p);
+    }
+}
+
+#[test]
+fn reader_internal_inlines_are_errors() {
+    for inline in [Inline::Temp(String::from("a")), Inline::None] {
+        let p = Pandoc { blocks: vec![Block::Para(vec![inline])], ..Default::default() };
+        assert!(LatexWriter::new().write(p.clone()).is_err());
+        assert!(TypstWriter::new().write(p).is_err());
+    }
+}